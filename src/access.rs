@@ -0,0 +1,485 @@
+//! Register wrappers that only expose the accesses the hardware allows
+
+use {Error, VolatileCell, Word};
+
+/// A [`VolatileCell`] that can only be read
+///
+/// Use this for status registers and other locations where a write is either ignored or has
+/// unintended side effects.
+///
+/// ```
+/// let status = vcell::ReadOnly::new(0x80u32);
+/// assert!(status.get_bit(7));
+/// ```
+///
+/// Writing is rejected at compile time:
+///
+/// ```compile_fail,E0599
+/// let status = vcell::ReadOnly::new(0x80u32);
+/// status.set(0);
+/// ```
+///
+/// [`VolatileCell`]: struct.VolatileCell.html
+#[repr(transparent)]
+pub struct ReadOnly<T> {
+    register: VolatileCell<T>,
+}
+
+impl<T> ReadOnly<T> {
+    /// Creates a new `ReadOnly` containing the given value
+    #[cfg(feature = "const-fn")]
+    pub const fn new(value: T) -> Self {
+        ReadOnly { register: VolatileCell::new(value) }
+    }
+
+    /// Creates a new `ReadOnly` containing the given value
+    ///
+    /// NOTE A `const fn` variant is available under the "const-fn" Cargo
+    /// feature
+    #[cfg(not(feature = "const-fn"))]
+    pub fn new(value: T) -> Self {
+        ReadOnly { register: VolatileCell::new(value) }
+    }
+
+    /// Returns a copy of the contained value
+    #[inline(always)]
+    pub fn get(&self) -> T
+        where T: Copy
+    {
+        self.register.get()
+    }
+
+    /// Reads a sub-field with the bit-manipulation-engine.
+    /// See [`VolatileCell::get_field`].
+    ///
+    /// [`VolatileCell::get_field`]: struct.VolatileCell.html#method.get_field
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn get_field(&self, first_bit: u8, bit_count: u8) -> T
+        where T: Copy
+    {
+        self.register.get_field(first_bit, bit_count)
+    }
+
+    /// Like [`get_field`](#method.get_field), but returns an error instead of panicking.
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn try_get_field(&self, first_bit: u8, bit_count: u8) -> Result<T, Error>
+        where T: Copy
+    {
+        self.register.try_get_field(first_bit, bit_count)
+    }
+
+    /// Returns a single bit of the register.
+    /// See [`VolatileCell::get_bit`].
+    ///
+    /// [`VolatileCell::get_bit`]: struct.VolatileCell.html#method.get_bit
+    #[inline(always)]
+    pub fn get_bit(&self, bit_to_get: u8) -> bool
+        where T: Word
+    {
+        self.register.get_bit(bit_to_get)
+    }
+
+    /// Like [`get_bit`](#method.get_bit), but returns an error instead of panicking
+    /// if the bit does not exist in `T`.
+    #[inline(always)]
+    pub fn try_get_bit(&self, bit_to_get: u8) -> Result<bool, Error>
+        where T: Word
+    {
+        self.register.try_get_bit(bit_to_get)
+    }
+}
+
+/// A [`VolatileCell`] that can only be written
+///
+/// Use this for command and data-out registers that read back as zero or as unrelated state.
+/// The bit-manipulation and bit-banding helpers are not available because the hardware performs
+/// them as a read-modify-write of the register.
+///
+/// ```
+/// let data = vcell::WriteOnly::new(0u8);
+/// data.set(0x55);
+/// ```
+///
+/// Reading, directly or through a read-modify-write, is rejected at compile time:
+///
+/// ```compile_fail,E0599
+/// let data = vcell::WriteOnly::new(0u8);
+/// let _ = data.get();
+/// ```
+///
+/// ```compile_fail,E0599
+/// let data = vcell::WriteOnly::new(0u8);
+/// data.set_bits(0x01);
+/// ```
+///
+/// [`VolatileCell`]: struct.VolatileCell.html
+#[repr(transparent)]
+pub struct WriteOnly<T> {
+    register: VolatileCell<T>,
+}
+
+impl<T> WriteOnly<T> {
+    /// Creates a new `WriteOnly` containing the given value
+    #[cfg(feature = "const-fn")]
+    pub const fn new(value: T) -> Self {
+        WriteOnly { register: VolatileCell::new(value) }
+    }
+
+    /// Creates a new `WriteOnly` containing the given value
+    ///
+    /// NOTE A `const fn` variant is available under the "const-fn" Cargo
+    /// feature
+    #[cfg(not(feature = "const-fn"))]
+    pub fn new(value: T) -> Self {
+        WriteOnly { register: VolatileCell::new(value) }
+    }
+
+    /// Sets the contained value
+    #[inline(always)]
+    pub fn set(&self, value: T)
+        where T: Copy
+    {
+        self.register.set(value)
+    }
+}
+
+/// A [`VolatileCell`] that can be both read and written
+///
+/// [`VolatileCell`]: struct.VolatileCell.html
+#[repr(transparent)]
+pub struct ReadWrite<T> {
+    register: VolatileCell<T>,
+}
+
+impl<T> ReadWrite<T> {
+    /// Creates a new `ReadWrite` containing the given value
+    #[cfg(feature = "const-fn")]
+    pub const fn new(value: T) -> Self {
+        ReadWrite { register: VolatileCell::new(value) }
+    }
+
+    /// Creates a new `ReadWrite` containing the given value
+    ///
+    /// NOTE A `const fn` variant is available under the "const-fn" Cargo
+    /// feature
+    #[cfg(not(feature = "const-fn"))]
+    pub fn new(value: T) -> Self {
+        ReadWrite { register: VolatileCell::new(value) }
+    }
+
+    /// Returns a copy of the contained value
+    #[inline(always)]
+    pub fn get(&self) -> T
+        where T: Copy
+    {
+        self.register.get()
+    }
+
+    /// Sets the contained value
+    #[inline(always)]
+    pub fn set(&self, value: T)
+        where T: Copy
+    {
+        self.register.set(value)
+    }
+
+    /// Replaces the contained value, and returns the old contained value
+    #[inline(always)]
+    pub fn replace(&self, value: T) -> T
+        where T: Copy
+    {
+        self.register.replace(value)
+    }
+
+    /// Swaps the values of two `ReadWrite`s
+    #[inline(always)]
+    pub fn swap(&self, other: &Self)
+        where T: Copy
+    {
+        self.register.swap(&other.register)
+    }
+
+    /// Takes the contained value, leaving `Default::default()` in its place
+    #[inline(always)]
+    pub fn take(&self) -> T
+        where T: Copy + Default
+    {
+        self.register.take()
+    }
+
+    /// Updates the contained value using a function.
+    /// See [`VolatileCell::update`].
+    ///
+    /// [`VolatileCell::update`]: struct.VolatileCell.html#method.update
+    #[inline(always)]
+    pub fn update<F>(&self, f: F)
+        where T: Copy,
+              F: FnOnce(T) -> T
+    {
+        self.register.update(f)
+    }
+
+    /// Performs a read-modify-write of the contained value.
+    /// See [`VolatileCell::modify`].
+    ///
+    /// [`VolatileCell::modify`]: struct.VolatileCell.html#method.modify
+    #[inline(always)]
+    pub fn modify<F>(&self, f: F)
+        where T: Copy,
+              F: FnOnce(T) -> T
+    {
+        self.register.modify(f)
+    }
+
+    /// Sets a sub-field of the contained value.
+    /// See [`VolatileCell::set_field`].
+    ///
+    /// [`VolatileCell::set_field`]: struct.VolatileCell.html#method.set_field
+    #[inline(always)]
+    pub fn set_field(&self, first_bit: u8, bit_count: u8, value: T)
+        where T: Word
+    {
+        self.register.set_field(first_bit, bit_count, value)
+    }
+
+    /// Like [`set_field`](#method.set_field), but returns an error instead of panicking.
+    #[inline(always)]
+    pub fn try_set_field(&self, first_bit: u8, bit_count: u8, value: T) -> Result<(), Error>
+        where T: Word
+    {
+        self.register.try_set_field(first_bit, bit_count, value)
+    }
+
+    /// Sets a sub-field, given as const generic parameters, of the contained value.
+    /// See [`VolatileCell::set_field_const`].
+    ///
+    /// [`VolatileCell::set_field_const`]: struct.VolatileCell.html#method.set_field_const
+    #[inline(always)]
+    pub fn set_field_const<const FIRST_BIT: u8, const BIT_COUNT: u8>(&self, value: T)
+        where T: Word
+    {
+        self.register.set_field_const::<FIRST_BIT, BIT_COUNT>(value)
+    }
+
+    /// Sets a collection of bits of the contained value.
+    /// See [`VolatileCell::set_bits`].
+    ///
+    /// [`VolatileCell::set_bits`]: struct.VolatileCell.html#method.set_bits
+    #[inline(always)]
+    pub fn set_bits(&self, bits_to_set: T)
+        where T: Word
+    {
+        self.register.set_bits(bits_to_set)
+    }
+
+    /// Clears a collection of bits of the contained value.
+    /// See [`VolatileCell::clear_bits`].
+    ///
+    /// [`VolatileCell::clear_bits`]: struct.VolatileCell.html#method.clear_bits
+    #[inline(always)]
+    pub fn clear_bits(&self, bits_to_clear: T)
+        where T: Word
+    {
+        self.register.clear_bits(bits_to_clear)
+    }
+
+    /// Inverts a collection of bits of the contained value.
+    /// See [`VolatileCell::invert_bits`].
+    ///
+    /// [`VolatileCell::invert_bits`]: struct.VolatileCell.html#method.invert_bits
+    #[inline(always)]
+    pub fn invert_bits(&self, bits_to_invert: T)
+        where T: Word
+    {
+        self.register.invert_bits(bits_to_invert)
+    }
+
+    /// Reads a single bit and clears it with the bit-manipulation-engine.
+    /// See [`VolatileCell::load_clear_bit`].
+    ///
+    /// [`VolatileCell::load_clear_bit`]: struct.VolatileCell.html#method.load_clear_bit
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn load_clear_bit(&self, bit: u8) -> T
+        where T: Copy
+    {
+        self.register.load_clear_bit(bit)
+    }
+
+    /// Like [`load_clear_bit`](#method.load_clear_bit), but returns an error instead of panicking.
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn try_load_clear_bit(&self, bit: u8) -> Result<T, Error>
+        where T: Copy
+    {
+        self.register.try_load_clear_bit(bit)
+    }
+
+    /// Reads a single bit and sets it with the bit-manipulation-engine.
+    /// See [`VolatileCell::load_set_bit`].
+    ///
+    /// [`VolatileCell::load_set_bit`]: struct.VolatileCell.html#method.load_set_bit
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn load_set_bit(&self, bit: u8) -> T
+        where T: Copy
+    {
+        self.register.load_set_bit(bit)
+    }
+
+    /// Like [`load_set_bit`](#method.load_set_bit), but returns an error instead of panicking.
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn try_load_set_bit(&self, bit: u8) -> Result<T, Error>
+        where T: Copy
+    {
+        self.register.try_load_set_bit(bit)
+    }
+
+    /// Reads a sub-field with the bit-manipulation-engine.
+    /// See [`VolatileCell::get_field`].
+    ///
+    /// [`VolatileCell::get_field`]: struct.VolatileCell.html#method.get_field
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn get_field(&self, first_bit: u8, bit_count: u8) -> T
+        where T: Copy
+    {
+        self.register.get_field(first_bit, bit_count)
+    }
+
+    /// Like [`get_field`](#method.get_field), but returns an error instead of panicking.
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn try_get_field(&self, first_bit: u8, bit_count: u8) -> Result<T, Error>
+        where T: Copy
+    {
+        self.register.try_get_field(first_bit, bit_count)
+    }
+
+    /// Sets a single bit of the register to `value`.
+    /// See [`VolatileCell::set_bit`].
+    ///
+    /// [`VolatileCell::set_bit`]: struct.VolatileCell.html#method.set_bit
+    #[inline(always)]
+    pub fn set_bit(&self, bit_to_set: u8, value: bool)
+        where T: Word
+    {
+        self.register.set_bit(bit_to_set, value)
+    }
+
+    /// Like [`set_bit`](#method.set_bit), but returns an error instead of panicking
+    /// if the bit does not exist in `T`.
+    #[inline(always)]
+    pub fn try_set_bit(&self, bit_to_set: u8, value: bool) -> Result<(), Error>
+        where T: Word
+    {
+        self.register.try_set_bit(bit_to_set, value)
+    }
+
+    /// Clears a single bit of the register.
+    /// See [`VolatileCell::clear_bit`].
+    ///
+    /// [`VolatileCell::clear_bit`]: struct.VolatileCell.html#method.clear_bit
+    #[inline(always)]
+    pub fn clear_bit(&self, bit_to_clear: u8)
+        where T: Word
+    {
+        self.register.clear_bit(bit_to_clear)
+    }
+
+    /// Like [`clear_bit`](#method.clear_bit), but returns an error instead of panicking
+    /// if the bit does not exist in `T`.
+    #[inline(always)]
+    pub fn try_clear_bit(&self, bit_to_clear: u8) -> Result<(), Error>
+        where T: Word
+    {
+        self.register.try_clear_bit(bit_to_clear)
+    }
+
+    /// Inverts a single bit of the register.
+    /// See [`VolatileCell::toggle_bit`].
+    ///
+    /// [`VolatileCell::toggle_bit`]: struct.VolatileCell.html#method.toggle_bit
+    #[inline(always)]
+    pub fn toggle_bit(&self, bit_to_toggle: u8)
+        where T: Word
+    {
+        self.register.toggle_bit(bit_to_toggle)
+    }
+
+    /// Like [`toggle_bit`](#method.toggle_bit), but returns an error instead of panicking
+    /// if the bit does not exist in `T`.
+    #[inline(always)]
+    pub fn try_toggle_bit(&self, bit_to_toggle: u8) -> Result<(), Error>
+        where T: Word
+    {
+        self.register.try_toggle_bit(bit_to_toggle)
+    }
+
+    /// Returns a single bit of the register.
+    /// See [`VolatileCell::get_bit`].
+    ///
+    /// [`VolatileCell::get_bit`]: struct.VolatileCell.html#method.get_bit
+    #[inline(always)]
+    pub fn get_bit(&self, bit_to_get: u8) -> bool
+        where T: Word
+    {
+        self.register.get_bit(bit_to_get)
+    }
+
+    /// Like [`get_bit`](#method.get_bit), but returns an error instead of panicking
+    /// if the bit does not exist in `T`.
+    #[inline(always)]
+    pub fn try_get_bit(&self, bit_to_get: u8) -> Result<bool, Error>
+        where T: Word
+    {
+        self.register.try_get_bit(bit_to_get)
+    }
+}
+
+#[cfg(test)]
+mod test_access {
+    use super::*;
+
+    #[test]
+    fn test_read_only() {
+        let status = ReadOnly::new(0x0000_0c80u32);
+        assert_eq!(status.get(), 0x0000_0c80);
+        assert!(status.get_bit(7));
+        assert!(!status.get_bit(8));
+        assert_eq!(status.try_get_bit(32), Err(Error::BitOutOfRange));
+    }
+
+    #[test]
+    fn test_write_only() {
+        let data = WriteOnly::new(0u16);
+        data.set(0xbeef);
+        assert_eq!(data.register.get(), 0xbeef);
+    }
+
+    #[test]
+    fn test_read_write() {
+        let ctrl = ReadWrite::new(0u32);
+        ctrl.set(0x0000_00f0);
+        ctrl.set_bits(0x0000_0001);
+        ctrl.clear_bits(0x0000_0010);
+        ctrl.invert_bits(0x0000_0300);
+        assert_eq!(ctrl.get(), 0x0000_03e1);
+        ctrl.set_field(8, 4, 0x0000_0500);
+        assert_eq!(ctrl.get(), 0x0000_05e1);
+        assert_eq!(ctrl.try_set_field(30, 4, 0), Err(Error::BadFieldWidth));
+        ctrl.set_bit(31, true);
+        ctrl.clear_bit(0);
+        ctrl.toggle_bit(1);
+        assert_eq!(ctrl.get(), 0x8000_05e2);
+        assert!(ctrl.get_bit(31));
+        assert_eq!(ctrl.try_toggle_bit(32), Err(Error::BitOutOfRange));
+        assert_eq!(ctrl.replace(7), 0x8000_05e2);
+        ctrl.modify(|v| v << 1);
+        assert_eq!(ctrl.take(), 14);
+        assert_eq!(ctrl.get(), 0);
+    }
+}
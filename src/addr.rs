@@ -0,0 +1,103 @@
+//! 32-bit physical addresses
+
+use core::fmt;
+
+use Error;
+
+/// A 32-bit physical address
+///
+/// The bit-manipulation-engine and bit-banding work on the 32-bit address map of the
+/// microcontroller, so their address math is done on this type rather than on `usize`. It
+/// behaves identically on the target and on a 64-bit host, where a pointer that does not fit in
+/// 32 bits is rejected instead of silently truncated.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct PhysAddr32(u32);
+
+impl PhysAddr32 {
+    /// Creates a new `PhysAddr32`
+    #[inline(always)]
+    pub const fn new(addr: u32) -> Self {
+        PhysAddr32(addr)
+    }
+
+    /// Returns the address as a `u32`
+    #[inline(always)]
+    pub const fn get(self) -> u32 {
+        self.0
+    }
+
+    /// Converts `addr`, or returns `AddressOutOfRegion` if it does not fit in 32 bits
+    #[inline(always)]
+    pub const fn try_from_usize(addr: usize) -> Result<Self, Error> {
+        if addr as u64 > u32::MAX as u64 {
+            return Err(Error::AddressOutOfRegion);
+        }
+        Ok(PhysAddr32(addr as u32))
+    }
+
+    /// Returns the address of `ptr`, or `AddressOutOfRegion` if it does not fit in 32 bits
+    #[inline(always)]
+    pub fn try_from_ptr<T>(ptr: *const T) -> Result<Self, Error> {
+        Self::try_from_usize(ptr as usize)
+    }
+
+    /// Returns the address as a `usize`
+    #[inline(always)]
+    pub const fn to_usize(self) -> usize {
+        self.0 as usize
+    }
+
+    /// Returns a pointer to the address
+    #[inline(always)]
+    pub fn to_ptr<T>(self) -> *mut T {
+        self.to_usize() as *mut T
+    }
+}
+
+impl From<u32> for PhysAddr32 {
+    #[inline(always)]
+    fn from(addr: u32) -> Self {
+        PhysAddr32(addr)
+    }
+}
+
+impl From<PhysAddr32> for u32 {
+    #[inline(always)]
+    fn from(addr: PhysAddr32) -> Self {
+        addr.0
+    }
+}
+
+impl fmt::Debug for PhysAddr32 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PhysAddr32({:#010x})", self.0)
+    }
+}
+
+impl fmt::LowerHex for PhysAddr32 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl fmt::UpperHex for PhysAddr32 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod test_addr {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        let addr = PhysAddr32::new(0x4000_1000);
+        assert_eq!(PhysAddr32::try_from_ptr(addr.to_ptr::<u32>()), Ok(addr));
+        assert_eq!(u32::from(addr), 0x4000_1000);
+        assert_eq!(PhysAddr32::try_from_usize(0xffff_ffff), Ok(PhysAddr32::new(!0)));
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(PhysAddr32::try_from_usize(0x1_0000_0000), Err(Error::AddressOutOfRegion));
+    }
+}
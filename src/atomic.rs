@@ -0,0 +1,643 @@
+//! Bit manipulation that is generic over the update mechanism
+//!
+//! [`AtomicBits`] is implemented by [`VolatileCell`] and [`ReadWrite`], which use the best
+//! mechanism enabled for the target, and by one adapter per mechanism for code that needs a
+//! specific one:
+//!
+//! - [`Bme`], the bit-manipulation-engine (`bit-manipulation` feature)
+//! - [`BitBand`], the bit-band alias regions (`bit-banding` feature)
+//! - [`OffsetAlias`], SET/CLEAR/INVERT register aliases (`offset-alias` feature)
+//! - [`CriticalSection`], a read-modify-write inside a critical section, which works everywhere
+//!
+//! Each adapter's `try_new` fails if its mechanism cannot reach the cell, so a driver can fall
+//! back to another one at run time.
+//!
+//! [`AtomicBits`]: trait.AtomicBits.html
+//! [`VolatileCell`]: ../struct.VolatileCell.html
+//! [`ReadWrite`]: ../struct.ReadWrite.html
+//! [`Bme`]: struct.Bme.html
+//! [`BitBand`]: struct.BitBand.html
+//! [`OffsetAlias`]: struct.OffsetAlias.html
+//! [`CriticalSection`]: struct.CriticalSection.html
+
+#[cfg(any(feature = "bit-manipulation", feature = "bit-banding", feature = "offset-alias"))]
+use core::marker::PhantomData;
+#[cfg(any(feature = "bit-manipulation", feature = "bit-banding", feature = "offset-alias"))]
+use core::{mem, ptr};
+
+#[cfg(feature = "bit-banding")]
+use bitband::{self, BitBandTarget};
+#[cfg(feature = "bit-manipulation")]
+use bme::{self, BmeTarget};
+#[cfg(feature = "bit-manipulation")]
+use BmeOperation;
+#[cfg(feature = "offset-alias")]
+use offset_alias::{self, OffsetAliasOperation, OffsetAliasTarget};
+#[cfg(any(feature = "bit-manipulation", feature = "bit-banding", feature = "offset-alias"))]
+use PhysAddr32;
+use {ops, Error, ReadWrite, VolatileCell, Word};
+
+/// Updates bits of a register without losing concurrent changes to its other bits
+pub trait AtomicBits<T: Word> {
+    /// Sets the bits that are set in `bits_to_set`
+    fn set_bits(&self, bits_to_set: T);
+
+    /// Clears the bits that are set in `bits_to_clear`
+    fn clear_bits(&self, bits_to_clear: T);
+
+    /// Inverts the bits that are set in `bits_to_invert`
+    fn invert_bits(&self, bits_to_invert: T);
+
+    /// Like [`write_field`](#method.write_field), but returns an error instead of panicking if
+    /// the field does not fit in `T` or the mechanism does not support it.
+    fn try_write_field(&self, first_bit: u8, bit_count: u8, value: T) -> Result<(), Error>;
+
+    /// Sets the `bit_count` bits starting at `first_bit` to the same bits of `value`
+    ///
+    /// `value` is given in place, that is already shifted left by `first_bit`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field does not fit in `T` or the mechanism does not support it.
+    #[inline(always)]
+    fn write_field(&self, first_bit: u8, bit_count: u8, value: T) {
+        if let Err(e) = self.try_write_field(first_bit, bit_count, value) {
+            panic!("Tried to set {} bits starting at bit {} of a {}-bit value: {}",
+                   bit_count, first_bit, T::BITS, e);
+        }
+    }
+}
+
+impl<T: Word> AtomicBits<T> for VolatileCell<T> {
+    #[inline(always)]
+    fn set_bits(&self, bits_to_set: T) {
+        unsafe { ops::set_bits(self.as_ptr(), bits_to_set) }
+    }
+
+    #[inline(always)]
+    fn clear_bits(&self, bits_to_clear: T) {
+        unsafe { ops::clear_bits(self.as_ptr(), bits_to_clear) }
+    }
+
+    #[inline(always)]
+    fn invert_bits(&self, bits_to_invert: T) {
+        unsafe { ops::invert_bits(self.as_ptr(), bits_to_invert) }
+    }
+
+    #[inline(always)]
+    fn try_write_field(&self, first_bit: u8, bit_count: u8, value: T) -> Result<(), Error> {
+        unsafe { ops::try_set_field(self.as_ptr(), first_bit, bit_count, value) }
+    }
+}
+
+impl<T: Word> AtomicBits<T> for ReadWrite<T> {
+    #[inline(always)]
+    fn set_bits(&self, bits_to_set: T) {
+        ReadWrite::set_bits(self, bits_to_set)
+    }
+
+    #[inline(always)]
+    fn clear_bits(&self, bits_to_clear: T) {
+        ReadWrite::clear_bits(self, bits_to_clear)
+    }
+
+    #[inline(always)]
+    fn invert_bits(&self, bits_to_invert: T) {
+        ReadWrite::invert_bits(self, bits_to_invert)
+    }
+
+    #[inline(always)]
+    fn try_write_field(&self, first_bit: u8, bit_count: u8, value: T) -> Result<(), Error> {
+        ReadWrite::try_set_field(self, first_bit, bit_count, value)
+    }
+}
+
+/// An update made by one of the hardware adapters
+#[cfg(any(feature = "bit-manipulation", feature = "bit-banding", feature = "offset-alias"))]
+#[derive(Clone, Copy)]
+enum Update<T> {
+    Set(T),
+    Clear(T),
+    Invert(T),
+    Field{first_bit: u8, bit_count: u8, value: T},
+}
+
+#[cfg(any(feature = "bit-manipulation", feature = "bit-banding", feature = "offset-alias"))]
+impl<T: Word> Update<T> {
+    #[inline(always)]
+    fn unwrap(self, addr: PhysAddr32, result: Result<(), Error>) {
+        if let Err(e) = result {
+            match self {
+                Update::Field{first_bit, bit_count, ..} => {
+                    panic!("Tried to set {} bits starting at bit {} of address {:?}: {}",
+                           bit_count, first_bit, addr, e)
+                },
+                _ => panic!("Tried to update bits of address {:?}: {}", addr, e),
+            }
+        }
+    }
+}
+
+/// The accesses of the hardware adapters, so that the tests can replay them on the emulator
+#[cfg(any(feature = "bit-manipulation", feature = "bit-banding", feature = "offset-alias"))]
+trait Bus<T> {
+    /// Stores `value` to `addr`
+    unsafe fn store(&mut self, addr: PhysAddr32, value: T);
+}
+
+/// A [`Bus`](trait.Bus.html) that can also load, for the adapters that read back
+#[cfg(feature = "bit-banding")]
+trait LoadBus<T>: Bus<T> {
+    /// Loads the value at `addr`
+    unsafe fn load(&mut self, addr: PhysAddr32) -> T;
+}
+
+/// The system bus, accessed with volatile loads and stores
+#[cfg(any(feature = "bit-manipulation", feature = "bit-banding", feature = "offset-alias"))]
+struct Volatile;
+
+#[cfg(any(feature = "bit-manipulation", feature = "bit-banding", feature = "offset-alias"))]
+impl<T> Bus<T> for Volatile {
+    #[inline(always)]
+    unsafe fn store(&mut self, addr: PhysAddr32, value: T) {
+        ptr::write_volatile(addr.to_ptr(), value)
+    }
+}
+
+#[cfg(feature = "bit-banding")]
+impl<T> LoadBus<T> for Volatile {
+    #[inline(always)]
+    unsafe fn load(&mut self, addr: PhysAddr32) -> T {
+        ptr::read_volatile(addr.to_ptr())
+    }
+}
+
+/// Updates a cell with the bit-manipulation-engine
+///
+/// Fields are written with a single BFI store, so they are limited to what the BME supports.
+#[cfg(feature = "bit-manipulation")]
+#[derive(Clone, Copy)]
+pub struct Bme<'a, T: 'a> {
+    addr: PhysAddr32,
+    _cell: PhantomData<&'a VolatileCell<T>>,
+}
+
+#[cfg(feature = "bit-manipulation")]
+impl<'a, T> Bme<'a, T> {
+    /// Creates a new `Bme` for `cell`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the BME cannot reach `cell` or does not support values of type `T`.
+    pub fn new(cell: &'a VolatileCell<T>) -> Self {
+        match Self::try_new(cell) {
+            Ok(bme) => bme,
+            Err(e) => panic!("Tried to use BME on address {:p}: {}", cell.as_ptr(), e),
+        }
+    }
+
+    /// Like [`new`](#method.new), but returns an error instead of panicking.
+    pub fn try_new(cell: &'a VolatileCell<T>) -> Result<Self, Error> {
+        let addr = PhysAddr32::try_from_ptr(cell.as_ptr())?;
+        bme::decorate_sized_on::<bme::Selected>(BmeOperation::Or, addr, mem::size_of::<T>())?;
+        Ok(Bme { addr, _cell: PhantomData })
+    }
+
+    /// Makes `update` to the value at `addr` with a single decorated store on the target `B`
+    #[inline(always)]
+    fn update_on<B: BmeTarget, M: Bus<T>>(bus: &mut M, addr: PhysAddr32, update: Update<T>)
+        -> Result<(), Error>
+        where T: Word
+    {
+        let (op, value) = match update {
+            Update::Set(bits) => (BmeOperation::Or, bits),
+            Update::Clear(bits) => (BmeOperation::And, !bits),
+            Update::Invert(bits) => (BmeOperation::Xor, bits),
+            Update::Field{first_bit, bit_count, value} => {
+                (BmeOperation::SetField{first_bit, bit_count}, value)
+            },
+        };
+        let decorated = bme::decorate_sized_on::<B>(op, addr, mem::size_of::<T>())?;
+        unsafe { bus.store(decorated, value) }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn try_update(&self, update: Update<T>) -> Result<(), Error>
+        where T: Word
+    {
+        Self::update_on::<bme::Selected, _>(&mut Volatile, self.addr, update)
+    }
+
+    #[inline(always)]
+    fn update(&self, update: Update<T>)
+        where T: Word
+    {
+        update.unwrap(self.addr, self.try_update(update))
+    }
+}
+
+#[cfg(feature = "bit-manipulation")]
+impl<'a, T: Word> AtomicBits<T> for Bme<'a, T> {
+    #[inline(always)]
+    fn set_bits(&self, bits_to_set: T) {
+        self.update(Update::Set(bits_to_set))
+    }
+
+    #[inline(always)]
+    fn clear_bits(&self, bits_to_clear: T) {
+        self.update(Update::Clear(bits_to_clear))
+    }
+
+    #[inline(always)]
+    fn invert_bits(&self, bits_to_invert: T) {
+        self.update(Update::Invert(bits_to_invert))
+    }
+
+    #[inline(always)]
+    fn try_write_field(&self, first_bit: u8, bit_count: u8, value: T) -> Result<(), Error> {
+        self.try_update(Update::Field{first_bit, bit_count, value})
+    }
+}
+
+/// Updates a cell through its bit-band alias words
+///
+/// Every bit is written with its own store to its alias word. The other bits of the cell are
+/// never disturbed, but a mask or field of several bits is not updated all at once.
+#[cfg(feature = "bit-banding")]
+#[derive(Clone, Copy)]
+pub struct BitBand<'a, T: 'a> {
+    addr: PhysAddr32,
+    _cell: PhantomData<&'a VolatileCell<T>>,
+}
+
+#[cfg(feature = "bit-banding")]
+impl<'a, T> BitBand<'a, T> {
+    /// Creates a new `BitBand` for `cell`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cell` is not in a bit-band region.
+    pub fn new(cell: &'a VolatileCell<T>) -> Self {
+        match Self::try_new(cell) {
+            Ok(bit_band) => bit_band,
+            Err(e) => panic!("Tried to use bit-banding on address {:p}: {}", cell.as_ptr(), e),
+        }
+    }
+
+    /// Like [`new`](#method.new), but returns an error instead of panicking.
+    pub fn try_new(cell: &'a VolatileCell<T>) -> Result<Self, Error> {
+        let addr = PhysAddr32::try_from_ptr(cell.as_ptr())?;
+        bitband::alias_sized_on::<bitband::Selected>(addr, mem::size_of::<T>(), 0)?;
+        Ok(BitBand { addr, _cell: PhantomData })
+    }
+
+    /// Makes `update` to the value at `addr` with one access per bit to the alias words of the
+    /// target `B`
+    #[inline(always)]
+    fn update_on<B: BitBandTarget, M: LoadBus<u32>>(bus: &mut M, addr: PhysAddr32, update: Update<T>)
+        -> Result<(), Error>
+        where T: Word
+    {
+        let (mask, value) = match update {
+            Update::Set(bits) => (bits, bits),
+            Update::Clear(bits) => (bits, T::ZERO),
+            Update::Invert(bits) => (bits, T::ZERO),
+            Update::Field{first_bit, bit_count, value} => {
+                (ops::field_mask::<T>(first_bit, bit_count)?, value)
+            },
+        };
+        for bit in 0..T::BITS as u8 {
+            let bit_mask = T::bit(bit);
+            if mask & bit_mask != bit_mask {
+                continue;
+            }
+            let alias = bitband::alias_sized_on::<B>(addr, mem::size_of::<T>(), usize::from(bit))?;
+            unsafe {
+                let new = match update {
+                    Update::Invert(_) => bus.load(alias) ^ 1,
+                    _ => u32::from(value & bit_mask == bit_mask),
+                };
+                bus.store(alias, new)
+            }
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn try_update(&self, update: Update<T>) -> Result<(), Error>
+        where T: Word
+    {
+        Self::update_on::<bitband::Selected, _>(&mut Volatile, self.addr, update)
+    }
+
+    #[inline(always)]
+    fn update(&self, update: Update<T>)
+        where T: Word
+    {
+        update.unwrap(self.addr, self.try_update(update))
+    }
+}
+
+#[cfg(feature = "bit-banding")]
+impl<'a, T: Word> AtomicBits<T> for BitBand<'a, T> {
+    #[inline(always)]
+    fn set_bits(&self, bits_to_set: T) {
+        self.update(Update::Set(bits_to_set))
+    }
+
+    #[inline(always)]
+    fn clear_bits(&self, bits_to_clear: T) {
+        self.update(Update::Clear(bits_to_clear))
+    }
+
+    #[inline(always)]
+    fn invert_bits(&self, bits_to_invert: T) {
+        self.update(Update::Invert(bits_to_invert))
+    }
+
+    #[inline(always)]
+    fn try_write_field(&self, first_bit: u8, bit_count: u8, value: T) -> Result<(), Error> {
+        self.try_update(Update::Field{first_bit, bit_count, value})
+    }
+}
+
+/// Updates a cell through its SET/CLEAR/INVERT register aliases
+///
+/// A field is written by clearing its zero bits and then setting its one bits, so it briefly
+/// holds the intersection of its old and new values.
+#[cfg(feature = "offset-alias")]
+#[derive(Clone, Copy)]
+pub struct OffsetAlias<'a, T: 'a> {
+    addr: PhysAddr32,
+    _cell: PhantomData<&'a VolatileCell<T>>,
+}
+
+#[cfg(feature = "offset-alias")]
+impl<'a, T> OffsetAlias<'a, T> {
+    /// Creates a new `OffsetAlias` for `cell`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cell` is not a 32-bit register with aliases on the selected target.
+    pub fn new(cell: &'a VolatileCell<T>) -> Self {
+        match Self::try_new(cell) {
+            Ok(alias) => alias,
+            Err(e) => panic!("Tried to use register aliases on address {:p}: {}", cell.as_ptr(), e),
+        }
+    }
+
+    /// Like [`new`](#method.new), but returns an error instead of panicking.
+    pub fn try_new(cell: &'a VolatileCell<T>) -> Result<Self, Error> {
+        offset_alias::try_alias_pointer(cell.as_ptr(), OffsetAliasOperation::Set)?;
+        Ok(OffsetAlias { addr: PhysAddr32::try_from_ptr(cell.as_ptr())?, _cell: PhantomData })
+    }
+
+    /// Makes `update` to the register at `addr` with stores to its aliases on the target `B`
+    #[inline(always)]
+    fn update_on<B: OffsetAliasTarget, M: Bus<T>>(bus: &mut M, addr: PhysAddr32, update: Update<T>)
+        -> Result<(), Error>
+        where T: Word
+    {
+        if mem::size_of::<T>() != 4 {
+            return Err(Error::UnsupportedSize);
+        }
+        let mut store = |operation, value| -> Result<(), Error> {
+            let alias = offset_alias::alias_on::<B>(addr, operation)?;
+            unsafe { bus.store(alias, value) }
+            Ok(())
+        };
+        match update {
+            Update::Set(bits) => store(OffsetAliasOperation::Set, bits),
+            Update::Clear(bits) => store(OffsetAliasOperation::Clear, bits),
+            Update::Invert(bits) => store(OffsetAliasOperation::Invert, bits),
+            Update::Field{first_bit, bit_count, value} => {
+                let mask = ops::field_mask::<T>(first_bit, bit_count)?;
+                store(OffsetAliasOperation::Clear, mask & !value)?;
+                store(OffsetAliasOperation::Set, mask & value)
+            },
+        }
+    }
+
+    #[inline(always)]
+    fn try_update(&self, update: Update<T>) -> Result<(), Error>
+        where T: Word
+    {
+        Self::update_on::<offset_alias::Selected, _>(&mut Volatile, self.addr, update)
+    }
+
+    #[inline(always)]
+    fn update(&self, update: Update<T>)
+        where T: Word
+    {
+        update.unwrap(self.addr, self.try_update(update))
+    }
+}
+
+#[cfg(feature = "offset-alias")]
+impl<'a, T: Word> AtomicBits<T> for OffsetAlias<'a, T> {
+    #[inline(always)]
+    fn set_bits(&self, bits_to_set: T) {
+        self.update(Update::Set(bits_to_set))
+    }
+
+    #[inline(always)]
+    fn clear_bits(&self, bits_to_clear: T) {
+        self.update(Update::Clear(bits_to_clear))
+    }
+
+    #[inline(always)]
+    fn invert_bits(&self, bits_to_invert: T) {
+        self.update(Update::Invert(bits_to_invert))
+    }
+
+    #[inline(always)]
+    fn try_write_field(&self, first_bit: u8, bit_count: u8, value: T) -> Result<(), Error> {
+        self.try_update(Update::Field{first_bit, bit_count, value})
+    }
+}
+
+/// Updates a cell with a volatile read-modify-write inside a critical section
+///
+/// This works for any cell, and is as strong as the `critical-section` implementation.
+#[derive(Clone, Copy)]
+pub struct CriticalSection<'a, T: 'a> {
+    cell: &'a VolatileCell<T>,
+}
+
+impl<'a, T> CriticalSection<'a, T> {
+    /// Creates a new `CriticalSection` for `cell`
+    pub fn new(cell: &'a VolatileCell<T>) -> Self {
+        CriticalSection { cell }
+    }
+}
+
+impl<'a, T: Word> AtomicBits<T> for CriticalSection<'a, T> {
+    #[inline(always)]
+    fn set_bits(&self, bits_to_set: T) {
+        unsafe { ops::read_modify_write(self.cell.as_ptr(), |v| v | bits_to_set) }
+    }
+
+    #[inline(always)]
+    fn clear_bits(&self, bits_to_clear: T) {
+        unsafe { ops::read_modify_write(self.cell.as_ptr(), |v| v & !bits_to_clear) }
+    }
+
+    #[inline(always)]
+    fn invert_bits(&self, bits_to_invert: T) {
+        unsafe { ops::read_modify_write(self.cell.as_ptr(), |v| v ^ bits_to_invert) }
+    }
+
+    #[inline(always)]
+    fn try_write_field(&self, first_bit: u8, bit_count: u8, value: T) -> Result<(), Error> {
+        let mask = ops::field_mask::<T>(first_bit, bit_count)?;
+        unsafe { ops::read_modify_write(self.cell.as_ptr(), |v| (v & !mask) | (value & mask)) }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_atomic {
+    use super::*;
+    #[cfg(feature = "bit-banding")]
+    use emulator::BitBandEmulator;
+    #[cfg(feature = "bit-manipulation")]
+    use emulator::BmeEmulator;
+    #[cfg(any(feature = "bit-manipulation", feature = "bit-banding"))]
+    use emulator::{Access, Memory};
+
+    fn exercise<A: AtomicBits<u32>>(bits: &A) {
+        bits.set_bits(0x0000_00ff);
+        bits.clear_bits(0x0000_000f);
+        bits.invert_bits(0x0000_0101);
+        bits.write_field(12, 4, 0xffff_a000);
+    }
+
+    /// The updates that `exercise` makes
+    #[cfg(any(feature = "bit-manipulation", feature = "bit-banding", feature = "offset-alias"))]
+    const UPDATES: [Update<u32>; 4] = [
+        Update::Set(0x0000_00ff),
+        Update::Clear(0x0000_000f),
+        Update::Invert(0x0000_0101),
+        Update::Field{first_bit: 12, bit_count: 4, value: 0xffff_a000},
+    ];
+
+    #[cfg(feature = "bit-manipulation")]
+    impl<'a, B: BmeTarget, T: Access> Bus<T> for BmeEmulator<'a, B> {
+        unsafe fn store(&mut self, addr: PhysAddr32, value: T) {
+            BmeEmulator::store(self, addr, value)
+        }
+    }
+
+    #[cfg(feature = "bit-banding")]
+    impl<'a, B: BitBandTarget, T: Access> Bus<T> for BitBandEmulator<'a, B> {
+        unsafe fn store(&mut self, addr: PhysAddr32, value: T) {
+            BitBandEmulator::store(self, addr, value)
+        }
+    }
+
+    #[cfg(feature = "bit-banding")]
+    impl<'a, B: BitBandTarget, T: Access> LoadBus<T> for BitBandEmulator<'a, B> {
+        unsafe fn load(&mut self, addr: PhysAddr32) -> T {
+            BitBandEmulator::load(self, addr)
+        }
+    }
+
+    /// Records the stores made to it
+    #[cfg(feature = "offset-alias")]
+    #[derive(Default)]
+    struct Recorder {
+        stores: [(u32, u32); 5],
+        count: usize,
+    }
+
+    #[cfg(feature = "offset-alias")]
+    impl Bus<u32> for Recorder {
+        unsafe fn store(&mut self, addr: PhysAddr32, value: u32) {
+            self.stores[self.count] = (addr.get(), value);
+            self.count += 1;
+        }
+    }
+
+    #[test]
+    fn test_best() {
+        let cell = VolatileCell::new(0u32);
+        exercise(&cell);
+        assert_eq!(cell.get(), 0x0000_a1f1);
+        assert_eq!(AtomicBits::try_write_field(&cell, 30, 4, 0), Err(Error::BadFieldWidth));
+    }
+
+    #[test]
+    fn test_critical_section() {
+        let cell = VolatileCell::new(0u32);
+        exercise(&CriticalSection::new(&cell));
+        assert_eq!(cell.get(), 0x0000_a1f1);
+        assert_eq!(CriticalSection::new(&cell).try_write_field(0, 0, 0), Err(Error::BadFieldWidth));
+    }
+
+    #[test]
+    #[cfg(feature = "bit-manipulation")]
+    fn test_bme_updates() {
+        let mut bytes = [0; 4];
+        let mut bme = BmeEmulator::<bme::Kinetis>::new(Memory::new(0x4000_1000, &mut bytes));
+        let addr = PhysAddr32::new(0x4000_1000);
+        for &update in UPDATES.iter() {
+            Bme::update_on::<bme::Kinetis, _>(&mut bme, addr, update).unwrap();
+        }
+        assert_eq!(bme.memory().load::<u32>(addr), 0x0000_a1f1);
+        let wide = Update::Field{first_bit: 0, bit_count: 17, value: 0u32};
+        assert_eq!(Bme::update_on::<bme::Kinetis, _>(&mut bme, addr, wide), Err(Error::BadFieldWidth));
+        let sram = PhysAddr32::new(0x1fff_f000);
+        assert_eq!(Bme::update_on::<bme::KinetisL, _>(&mut bme, sram, UPDATES[0]),
+                   Err(Error::AddressOutOfRegion));
+    }
+
+    #[test]
+    #[cfg(feature = "bit-manipulation")]
+    fn test_bme_unreachable() {
+        let cell = VolatileCell::new(0u32);
+        assert_eq!(Bme::try_new(&cell).err(), Some(Error::AddressOutOfRegion));
+    }
+
+    #[test]
+    #[cfg(feature = "bit-banding")]
+    fn test_bitband_updates() {
+        let mut bytes = [0; 4];
+        let mut bb = BitBandEmulator::<bitband::CortexM>::new(Memory::new(0x2000_0100, &mut bytes));
+        let addr = PhysAddr32::new(0x2000_0100);
+        for &update in UPDATES.iter() {
+            BitBand::update_on::<bitband::CortexM, _>(&mut bb, addr, update).unwrap();
+        }
+        assert_eq!(bb.memory().load::<u32>(addr), 0x0000_a1f1);
+        assert_eq!(BitBand::update_on::<bitband::NoBitBand, _>(&mut bb, addr, UPDATES[0]),
+                   Err(Error::AddressOutOfRegion));
+    }
+
+    #[test]
+    #[cfg(feature = "bit-banding")]
+    fn test_bitband_unreachable() {
+        let cell = VolatileCell::new(0u32);
+        assert_eq!(BitBand::try_new(&cell).err(), Some(Error::AddressOutOfRegion));
+    }
+
+    #[test]
+    #[cfg(feature = "offset-alias")]
+    fn test_offset_alias_updates() {
+        use offset_alias::Rp2040;
+
+        let addr = PhysAddr32::new(0x4001_4000);
+        let alias = |operation| offset_alias::alias_on::<Rp2040>(addr, operation).unwrap().get();
+        let mut recorder = Recorder::default();
+        for &update in UPDATES.iter() {
+            OffsetAlias::update_on::<Rp2040, _>(&mut recorder, addr, update).unwrap();
+        }
+        assert_eq!(recorder.count, 5);
+        assert_eq!(recorder.stores, [
+            (alias(OffsetAliasOperation::Set), 0x0000_00ff),
+            (alias(OffsetAliasOperation::Clear), 0x0000_000f),
+            (alias(OffsetAliasOperation::Invert), 0x0000_0101),
+            (alias(OffsetAliasOperation::Clear), 0x0000_5000),
+            (alias(OffsetAliasOperation::Set), 0x0000_a000),
+        ]);
+    }
+}
@@ -0,0 +1,427 @@
+//! Cortex-M3/M4 bit-banding
+//!
+//! Each bit of the first 1 MiB of SRAM and of the peripheral space is mirrored as a whole word
+//! in a 32 MiB alias region: writing 0 or 1 to the alias word clears or sets the bit, and
+//! reading it returns the bit, without a read-modify-write of the rest of the word.
+//! See [ARM documentation] on bit-banding.
+//!
+//! Cortex-M3 and Cortex-M4 cores bit-band the first 1 MiB of the SRAM and of the peripheral
+//! regions, but vendors may leave one of them out, and Cortex-M0, M7 and M33 cores have no
+//! bit-banding at all. The regions are described by a [`BitBandTarget`]; the one used by
+//! [`VolatileCell`] and friends is [`Selected`], which is chosen with one of the `bitband-*`
+//! Cargo features and defaults to [`CortexM`]. If several of them are enabled, the first of
+//! `bitband-none`, `bitband-sram-only` and `bitband-peripherals-only` wins.
+//!
+//! [ARM documentation]: http://infocenter.arm.com/help/index.jsp?topic=/com.arm.doc.ddi0337h/Behcjiic.html
+//! [`BitBandTarget`]: trait.BitBandTarget.html
+//! [`VolatileCell`]: ../struct.VolatileCell.html
+//! [`Selected`]: type.Selected.html
+//! [`CortexM`]: struct.CortexM.html
+
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::{mem, slice};
+
+use {AddressRange, Error, PhysAddr32, VolatileCell, Word};
+
+/// A bit-banded region, and the base of its alias region
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitBandRegion {
+    /// The addresses whose bits are mirrored
+    ///
+    /// Only the address of a value is checked against the range, so `end` should be aligned at
+    /// least to the size of the largest value accessed near it.
+    pub source: AddressRange,
+    /// The address of the alias word of bit 0 of `source.start`
+    pub alias: PhysAddr32,
+}
+
+impl BitBandRegion {
+    /// Creates a new `BitBandRegion`
+    pub const fn new(source: AddressRange, alias: u32) -> Self {
+        BitBandRegion { source, alias: PhysAddr32::new(alias) }
+    }
+}
+
+/// Description of the bit-banded regions of a part
+pub trait BitBandTarget {
+    /// The bit-banded regions; empty if the part has no bit-banding
+    const REGIONS: &'static [BitBandRegion];
+}
+
+const SRAM: BitBandRegion =
+    BitBandRegion::new(AddressRange::new(0x2000_0000, 0x2010_0000), 0x2200_0000);
+const PERIPHERALS: BitBandRegion =
+    BitBandRegion::new(AddressRange::new(0x4000_0000, 0x4010_0000), 0x4200_0000);
+
+/// Any Cortex-M3 or Cortex-M4 part: the first 1 MiB of SRAM and of the peripheral space
+///
+/// This is the default [`Selected`](type.Selected.html) target.
+pub struct CortexM;
+
+impl BitBandTarget for CortexM {
+    const REGIONS: &'static [BitBandRegion] = &[SRAM, PERIPHERALS];
+}
+
+/// A part that only bit-bands the SRAM region
+pub struct SramOnly;
+
+impl BitBandTarget for SramOnly {
+    const REGIONS: &'static [BitBandRegion] = &[SRAM];
+}
+
+/// A part that only bit-bands the peripheral region
+pub struct PeripheralsOnly;
+
+impl BitBandTarget for PeripheralsOnly {
+    const REGIONS: &'static [BitBandRegion] = &[PERIPHERALS];
+}
+
+/// A part without bit-banding (Cortex-M0, M0+, M7, M23, M33, ...)
+///
+/// Every bit-band operation of [`VolatileCell`](../struct.VolatileCell.html) falls back to its
+/// software implementation.
+pub struct NoBitBand;
+
+impl BitBandTarget for NoBitBand {
+    const REGIONS: &'static [BitBandRegion] = &[];
+}
+
+select_target! {
+    /// The target used by the bit-band operations of [`VolatileCell`](../struct.VolatileCell.html)
+    /// and friends
+    default CortexM,
+    "bitband-none" => NoBitBand,
+    "bitband-sram-only" => SramOnly,
+    "bitband-peripherals-only" => PeripheralsOnly
+}
+
+/// Returns the alias word of bit `bit` of the 32-bit word at `addr`, on the
+/// [`Selected`](type.Selected.html) target
+#[inline(always)]
+pub const fn alias(addr: PhysAddr32, bit: u8) -> Result<PhysAddr32, Error> {
+    alias_on::<Selected>(addr, bit)
+}
+
+/// Like [`alias`](fn.alias.html), on the target `B`
+#[inline(always)]
+pub const fn alias_on<B: BitBandTarget>(addr: PhysAddr32, bit: u8) -> Result<PhysAddr32, Error> {
+    alias_sized_on::<B>(addr, 4, bit as usize)
+}
+
+/// Returns the alias word of bit `bit` of the `size`-byte value at `addr` on the target `B`
+#[inline(always)]
+pub(crate) const fn alias_sized_on<B: BitBandTarget>(addr: PhysAddr32, size: usize, bit: usize)
+    -> Result<PhysAddr32, Error>
+{
+    let mut i = 0;
+    while i < B::REGIONS.len() {
+        let region = &B::REGIONS[i];
+        if region.source.contains(addr) {
+            if bit >= size * 8 {
+                return Err(Error::BitOutOfRange);
+            }
+            // Each bit of the source expands to a 32-bit word in the alias region
+            let offset = ((addr.get() - region.source.start.get()) << 5) + ((bit as u32) << 2);
+            return Ok(PhysAddr32::new(region.alias.get() + offset));
+        }
+        i += 1;
+    }
+    Err(Error::AddressOutOfRegion)
+}
+
+/// Like [`alias_on`](fn.alias_on.html), for a pointer to a `T`
+#[inline(always)]
+pub(crate) fn try_alias_on<B: BitBandTarget, T>(addr: *mut T, bit: u8) -> Result<*mut u32, Error> {
+    let addr = PhysAddr32::try_from_ptr(addr)?;
+    alias_sized_on::<B>(addr, mem::size_of::<T>(), usize::from(bit)).map(PhysAddr32::to_ptr)
+}
+
+/// Returns the 32-bit word and the bit within it that the alias word at `alias` refers to, on
+/// the [`Selected`](type.Selected.html) target
+#[inline]
+pub fn decode(alias: PhysAddr32) -> Result<(PhysAddr32, u8), Error> {
+    decode_on::<Selected>(alias)
+}
+
+/// Like [`decode`](fn.decode.html), on the target `B`
+pub fn decode_on<B: BitBandTarget>(alias: PhysAddr32) -> Result<(PhysAddr32, u8), Error> {
+    let alias = alias.get();
+    for region in B::REGIONS {
+        let alias_start = region.alias.get();
+        let alias_end = alias_start + ((region.source.end.get() - region.source.start.get()) << 5);
+        if alias >= alias_start && alias < alias_end && alias & 0x3 == 0 {
+            let bit_number = (alias - alias_start) >> 2;
+            let byte = region.source.start.get() + (bit_number >> 3);
+            return Ok((PhysAddr32::new(byte & !0x3), ((byte & 0x3) * 8 + (bit_number & 0x7)) as u8));
+        }
+    }
+    Err(Error::AddressOutOfRegion)
+}
+
+/// The bit-band alias word of one bit of a `VolatileCell`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitBandAlias {
+    addr: PhysAddr32,
+}
+
+impl BitBandAlias {
+    /// Returns the alias word of bit `bit` of `cell`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit or the address is not supported; see [`try_new`](#method.try_new).
+    #[inline(always)]
+    pub fn new<T>(cell: &VolatileCell<T>, bit: u8) -> Self {
+        Self::from_ptr(VolatileCell::bitband_pointer(cell.as_ptr(), bit))
+    }
+
+    /// Like [`new`](#method.new), but returns an error instead of panicking if the bit or the
+    /// address is not supported.
+    #[inline(always)]
+    pub fn try_new<T>(cell: &VolatileCell<T>, bit: u8) -> Result<Self, Error> {
+        Ok(Self::from_ptr(VolatileCell::try_bitband_pointer(cell.as_ptr(), bit)?))
+    }
+
+    /// Like [`try_new`](#method.try_new), on the target `B` instead of the
+    /// [`Selected`](type.Selected.html) one
+    #[inline(always)]
+    pub fn try_new_on<B: BitBandTarget, T>(cell: &VolatileCell<T>, bit: u8) -> Result<Self, Error> {
+        Ok(Self::from_ptr(try_alias_on::<B, T>(cell.as_ptr(), bit)?))
+    }
+
+    #[inline(always)]
+    fn from_ptr(ptr: *mut u32) -> Self {
+        // The alias was computed from a `PhysAddr32`, so it fits in 32 bits
+        BitBandAlias { addr: PhysAddr32::new(ptr as usize as u32) }
+    }
+
+    /// Returns the alias word of bit `bit` of the 32-bit word at `addr`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit or the address is not supported; see
+    /// [`try_from_addr`](#method.try_from_addr).
+    #[inline(always)]
+    pub const fn from_addr(addr: PhysAddr32, bit: u8) -> Self {
+        match Self::try_from_addr(addr, bit) {
+            Ok(alias) => alias,
+            Err(_) => panic!("Tried to use bit-banding on a bit or address it does not support"),
+        }
+    }
+
+    /// Like [`from_addr`](#method.from_addr), but returns an error instead of panicking if the
+    /// bit or the address is not supported.
+    #[inline(always)]
+    pub const fn try_from_addr(addr: PhysAddr32, bit: u8) -> Result<Self, Error> {
+        Self::try_from_addr_on::<Selected>(addr, bit)
+    }
+
+    /// Like [`try_from_addr`](#method.try_from_addr), on the target `B` instead of the
+    /// [`Selected`](type.Selected.html) one
+    #[inline(always)]
+    pub const fn try_from_addr_on<B: BitBandTarget>(addr: PhysAddr32, bit: u8) -> Result<Self, Error> {
+        match alias_on::<B>(addr, bit) {
+            Ok(addr) => Ok(BitBandAlias { addr }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the address of the alias word
+    #[inline(always)]
+    pub const fn addr(&self) -> PhysAddr32 {
+        self.addr
+    }
+
+    /// Returns the alias word as a pointer
+    ///
+    /// The CPU can access it too, with 32-bit volatile loads and stores of 0 or 1.
+    #[inline(always)]
+    pub fn as_ptr(&self) -> *mut u32 {
+        self.addr.to_ptr()
+    }
+}
+
+/// A `VolatileCell` seen as an array of bit-band alias words, one per bit
+///
+/// The alias words of consecutive bits are consecutive words, so the view dereferences to a
+/// `[VolatileCell<u32>]` with `size_of::<T>() * 8` elements: element `i` reads as 0 or 1 and
+/// sets or clears bit `i` when written, and can be handed out on its own as a
+/// `&VolatileCell<u32>`.
+pub struct BitBandView<'a, T: 'a> {
+    aliases: &'a [VolatileCell<u32>],
+    _marker: PhantomData<&'a VolatileCell<T>>,
+}
+
+impl<'a, T> BitBandView<'a, T> {
+    /// Returns the view of `cell`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cell` is outside of the bit-banded regions; see [`try_new`](#method.try_new).
+    #[inline(always)]
+    pub fn new(cell: &'a VolatileCell<T>) -> Self {
+        Self::from_alias(BitBandAlias::new(cell, 0))
+    }
+
+    /// Like [`new`](#method.new), but returns an error instead of panicking if `cell` is outside
+    /// of the bit-banded regions.
+    #[inline(always)]
+    pub fn try_new(cell: &'a VolatileCell<T>) -> Result<Self, Error> {
+        Ok(Self::from_alias(BitBandAlias::try_new(cell, 0)?))
+    }
+
+    #[inline(always)]
+    fn from_alias(first: BitBandAlias) -> Self {
+        BitBandView {
+            aliases: unsafe { slice::from_raw_parts(first.as_ptr() as *const VolatileCell<u32>,
+                                                    mem::size_of::<T>() * 8) },
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the numbers of the bits that are set
+    ///
+    /// Each bit is read through its alias word as the iterator reaches it.
+    #[inline(always)]
+    pub fn iter_set(&self) -> impl Iterator<Item = usize> + 'a {
+        self.aliases.iter().enumerate().filter(|&(_, alias)| alias.get() != 0).map(|(bit, _)| bit)
+    }
+
+    /// Sets every bit that is set in `mask`, one alias word write per bit
+    ///
+    /// Unlike [`VolatileCell::set_bits`] this never reads the value, so it can be used on
+    /// registers where reading has side effects.
+    ///
+    /// [`VolatileCell::set_bits`]: ../struct.VolatileCell.html#method.set_bits
+    #[inline(always)]
+    pub fn set_bits(&self, mask: T)
+        where T: Word
+    {
+        self.write_bits(mask, 1)
+    }
+
+    /// Clears every bit that is set in `mask`, one alias word write per bit
+    #[inline(always)]
+    pub fn clear_bits(&self, mask: T)
+        where T: Word
+    {
+        self.write_bits(mask, 0)
+    }
+
+    #[inline(always)]
+    fn write_bits(&self, mask: T, value: u32)
+        where T: Word
+    {
+        for (bit, alias) in self.aliases.iter().enumerate() {
+            let bit = T::bit(bit as u8);
+            if mask & bit == bit {
+                alias.set(value)
+            }
+        }
+    }
+}
+
+impl<'a, T> Deref for BitBandView<'a, T> {
+    type Target = [VolatileCell<u32>];
+
+    #[inline(always)]
+    fn deref(&self) -> &[VolatileCell<u32>] {
+        self.aliases
+    }
+}
+
+impl<'a, T> Clone for BitBandView<'a, T> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for BitBandView<'a, T> {}
+
+#[cfg(test)]
+mod test_bitband {
+    use super::*;
+
+    fn alias_of(addr: u32, bit: u8) -> Result<u32, Error> {
+        BitBandAlias::try_from_addr_on::<CortexM>(PhysAddr32::new(addr), bit).map(|a| a.addr().get())
+    }
+
+    #[test]
+    fn test_alias() {
+        let alias = BitBandAlias::try_from_addr_on::<CortexM>(PhysAddr32::new(0x4001_080c), 5).unwrap();
+        assert_eq!(alias.addr(), PhysAddr32::new(0x4221_0194));
+        assert_eq!(alias.as_ptr(), 0x4221_0194usize as *mut u32);
+        assert_eq!(alias_of(0x2000_0000, 31), Ok(0x2200_007c));
+        assert_eq!(alias_of(0x2000_0000, 32), Err(Error::BitOutOfRange));
+        assert_eq!(alias_of(0x4010_0000, 0), Err(Error::AddressOutOfRegion));
+        // Bit 16 of the word at 0x2000_0002 is bit 0 of the byte at 0x2000_0004
+        assert_eq!(alias_of(0x2000_0002, 16), Ok(0x2200_0080));
+        assert_eq!(alias_of(0x2000_0002, 16), alias_of(0x2000_0004, 0));
+        assert_eq!(try_alias_on::<CortexM, u16>(0x2000_0002usize as *mut u16, 15),
+                   Ok(0x2200_007cusize as *mut u32));
+        assert_eq!(try_alias_on::<CortexM, u16>(0x2000_0002usize as *mut u16, 16),
+                   Err(Error::BitOutOfRange));
+        let cell = VolatileCell::new(0u32);
+        assert_eq!(BitBandAlias::try_new_on::<CortexM, _>(&cell, 0), Err(Error::AddressOutOfRegion));
+    }
+
+    #[test]
+    fn test_alias_is_shareable() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<BitBandAlias>();
+    }
+
+    #[test]
+    fn test_view() {
+        // The view indexes consecutive alias words
+        assert_eq!(alias_of(0x4001_080c, 9), alias_of(0x4001_080c, 0).map(|a| a + 9 * 4));
+        let cell = VolatileCell::new(0u16);
+        assert!(BitBandView::try_new(&cell).is_err());
+    }
+
+    #[test]
+    fn test_targets() {
+        let alias_on = |addr, bit, target: fn(PhysAddr32, u8) -> Result<BitBandAlias, Error>| {
+            target(PhysAddr32::new(addr), bit).map(|a| a.addr().get())
+        };
+        assert_eq!(alias_on(0x2000_0100, 1, BitBandAlias::try_from_addr_on::<SramOnly>),
+                   Ok(0x2200_2004));
+        assert_eq!(alias_on(0x4000_0100, 1, BitBandAlias::try_from_addr_on::<SramOnly>),
+                   Err(Error::AddressOutOfRegion));
+        assert_eq!(alias_on(0x4000_0100, 1, BitBandAlias::try_from_addr_on::<PeripheralsOnly>),
+                   Ok(0x4200_2004));
+        assert_eq!(alias_on(0x2000_0100, 1, BitBandAlias::try_from_addr_on::<NoBitBand>),
+                   Err(Error::AddressOutOfRegion));
+
+        assert_eq!(try_alias_on::<CortexM, u64>(0x2000_0008usize as *mut u64, 40),
+                   Ok((0x2200_0000 + 0x8 * 32 + 40 * 4) as *mut u32));
+    }
+
+    #[test]
+    fn test_decode() {
+        let decode = |alias| decode_on::<CortexM>(PhysAddr32::new(alias)).map(|(word, bit)| (word.get(), bit));
+        assert_eq!(decode(0x4221_0184), Ok((0x4001_080c, 1)));
+        assert_eq!(decode(0x2200_007c), Ok((0x2000_0000, 31)));
+        assert_eq!(decode(0x2200_0080), Ok((0x2000_0004, 0)));
+        assert_eq!(decode(0x4221_0186), Err(Error::AddressOutOfRegion));
+        assert_eq!(decode(0x4001_080c), Err(Error::AddressOutOfRegion));
+        assert_eq!(decode_on::<SramOnly>(PhysAddr32::new(0x4221_0184)), Err(Error::AddressOutOfRegion));
+        for &(addr, bit) in &[(0x2000_0000, 0), (0x2000_0104, 17), (0x400f_fffc, 31)] {
+            assert_eq!(decode(alias_of(addr, bit).unwrap()), Ok((addr, bit)));
+        }
+    }
+
+    #[test]
+    fn test_const_alias() {
+        const LED: PhysAddr32 = match alias_on::<CortexM>(PhysAddr32::new(0x4001_080c), 5) {
+            Ok(alias) => alias,
+            Err(_) => panic!(),
+        };
+        assert_eq!(LED, PhysAddr32::new(0x4221_0194));
+        assert_eq!(alias_on::<CortexM>(PhysAddr32::new(0x4001_080c), 32), Err(Error::BitOutOfRange));
+        assert_eq!(alias_on::<NoBitBand>(PhysAddr32::new(0x4001_080c), 5),
+                   Err(Error::AddressOutOfRegion));
+    }
+}
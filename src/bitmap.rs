@@ -0,0 +1,164 @@
+//! A bitset whose bits are updated through the bit-band alias region
+
+use {Error, VolatileCell};
+
+/// A set of `N * 32` flags, each updated with a single store to its bit-band alias word
+///
+/// Allocation bitmaps and event flags shared with interrupt handlers normally need a critical
+/// section around every update, because setting one bit is a read-modify-write of the whole
+/// word. When the bitmap is placed in the bit-banded SRAM region (for example with a
+/// `#[link_section]` attribute), [`set`], [`clear`] and [`test`] are a single store or load of
+/// the bit's alias word instead, so they can be used from any context without masking
+/// interrupts.
+///
+/// Outside of the bit-banded regions the same operations fall back to a read-modify-write
+/// inside a critical section, like [`VolatileCell::set_bit`].
+///
+/// [`set`]: #method.set
+/// [`clear`]: #method.clear
+/// [`test`]: #method.test
+/// [`VolatileCell::set_bit`]: struct.VolatileCell.html#method.set_bit
+pub struct BitBandBitmap<const N: usize> {
+    words: [VolatileCell<u32>; N],
+}
+
+impl<const N: usize> BitBandBitmap<N> {
+    /// The number of flags in the bitmap
+    pub const BITS: usize = N * 32;
+
+    /// Creates a new `BitBandBitmap` with every flag clear
+    pub const fn new() -> Self {
+        BitBandBitmap { words: [const { VolatileCell::const_new(0) }; N] }
+    }
+
+    #[inline(always)]
+    fn word(&self, bit: usize) -> Result<(&VolatileCell<u32>, u8), Error> {
+        match self.words.get(bit / 32) {
+            Some(word) => Ok((word, (bit % 32) as u8)),
+            None => Err(Error::BitOutOfRange),
+        }
+    }
+
+    #[inline(always)]
+    fn unwrap<R>(result: Result<R, Error>, bit: usize) -> R {
+        match result {
+            Ok(r) => r,
+            Err(_) => panic!("Tried to access flag {} of a bitmap of {} flags", bit, Self::BITS),
+        }
+    }
+
+    /// Sets flag `bit`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit` is not less than [`BITS`](#associatedconstant.BITS).
+    #[inline(always)]
+    pub fn set(&self, bit: usize) {
+        Self::unwrap(self.try_set(bit), bit)
+    }
+
+    /// Like [`set`](#method.set), but returns an error instead of panicking.
+    #[inline(always)]
+    pub fn try_set(&self, bit: usize) -> Result<(), Error> {
+        let (word, bit) = self.word(bit)?;
+        word.try_set_bit(bit, true)
+    }
+
+    /// Clears flag `bit`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit` is not less than [`BITS`](#associatedconstant.BITS).
+    #[inline(always)]
+    pub fn clear(&self, bit: usize) {
+        Self::unwrap(self.try_clear(bit), bit)
+    }
+
+    /// Like [`clear`](#method.clear), but returns an error instead of panicking.
+    #[inline(always)]
+    pub fn try_clear(&self, bit: usize) -> Result<(), Error> {
+        let (word, bit) = self.word(bit)?;
+        word.try_clear_bit(bit)
+    }
+
+    /// Returns whether flag `bit` is set
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit` is not less than [`BITS`](#associatedconstant.BITS).
+    #[inline(always)]
+    pub fn test(&self, bit: usize) -> bool {
+        Self::unwrap(self.try_test(bit), bit)
+    }
+
+    /// Like [`test`](#method.test), but returns an error instead of panicking.
+    #[inline(always)]
+    pub fn try_test(&self, bit: usize) -> Result<bool, Error> {
+        let (word, bit) = self.word(bit)?;
+        word.try_get_bit(bit)
+    }
+
+    /// Returns the lowest flag that is set, if any
+    ///
+    /// The words are read one after the other, so flags changed concurrently may or may not be
+    /// seen.
+    pub fn first_set(&self) -> Option<usize> {
+        self.iter().next()
+    }
+
+    /// Returns an iterator over the flags that are set, in increasing order
+    ///
+    /// Each word is read once, when the iterator reaches it.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, word)| {
+            let mut bits = word.get();
+            core::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let bit = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some(i * 32 + bit)
+            })
+        })
+    }
+}
+
+impl<const N: usize> Default for BitBandBitmap<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Inside the bit-banded regions every update is a single bus cycle; outside of them updates are
+// done in a critical section.
+unsafe impl<const N: usize> Sync for BitBandBitmap<N> {}
+
+#[cfg(test)]
+mod test_bitmap {
+    use super::*;
+
+    #[test]
+    fn test_flags() {
+        let bitmap = BitBandBitmap::<2>::new();
+        assert_eq!(BitBandBitmap::<2>::BITS, 64);
+        assert_eq!(bitmap.first_set(), None);
+        bitmap.set(40);
+        bitmap.set(3);
+        bitmap.set(63);
+        assert!(bitmap.test(40));
+        assert!(!bitmap.test(41));
+        assert_eq!(bitmap.first_set(), Some(3));
+        bitmap.clear(3);
+        assert!(bitmap.iter().eq([40, 63].iter().cloned()));
+        assert_eq!(bitmap.try_set(64), Err(Error::BitOutOfRange));
+        assert_eq!(bitmap.try_test(64), Err(Error::BitOutOfRange));
+    }
+
+    #[test]
+    fn test_static() {
+        static FLAGS: BitBandBitmap<1> = BitBandBitmap::new();
+        FLAGS.set(7);
+        assert!(FLAGS.test(7));
+    }
+}
@@ -0,0 +1,637 @@
+//! The Kinetis bit-manipulation-engine (BME)
+//!
+//! The BME performs read-modify-write operations, and a few decorated loads, on behalf of the
+//! core: the operation is encoded into otherwise-unused bits of the address, and the BME turns
+//! the single decorated access into the appropriate sequence on the bus.
+//! See [NXP documentation] on the BME.
+//!
+//! Every Kinetis part with a BME uses the same decoration encoding, but the address ranges it
+//! can decorate differ between families. Those are described by a [`BmeTarget`]; the one used
+//! by [`VolatileCell`] and friends is [`Selected`], which is chosen with one of the
+//! `bme-kinetis-*` Cargo features and defaults to [`Kinetis`]. If several of them are enabled,
+//! the first of `bme-kinetis-l`, `bme-kinetis-v`, `bme-kinetis-m` and `bme-kinetis-e` wins.
+//!
+//! [NXP documentation]: https://www.nxp.com/docs/en/application-note/AN4838.pdf
+//! [`BmeTarget`]: trait.BmeTarget.html
+//! [`VolatileCell`]: ../struct.VolatileCell.html
+//! [`Selected`]: type.Selected.html
+//! [`Kinetis`]: struct.Kinetis.html
+
+use core::marker::PhantomData;
+use core::mem;
+
+use {AddressRange, Error, PhysAddr32, VolatileCell};
+
+/// Description of the address ranges a part's BME can decorate
+pub trait BmeTarget {
+    /// The source address ranges (not the decorated aliases) that the BME can operate on
+    ///
+    /// Operations whose decoration overlaps the address offset are further restricted: the
+    /// field operations (BFI and UBFX) can only reach the lower 512 KiB of each 1 MiB window,
+    /// and the other operations the whole 1 MiB.
+    const REGIONS: &'static [AddressRange];
+}
+
+/// Any Kinetis part: the whole 1 MiB windows at the start of SRAM_U and of the peripheral space
+///
+/// This is the most permissive description, and the default [`Selected`](type.Selected.html)
+/// target.
+pub struct Kinetis;
+
+impl BmeTarget for Kinetis {
+    const REGIONS: &'static [AddressRange] = &[
+        AddressRange::new(0x2000_0000, 0x2010_0000),
+        AddressRange::new(0x4000_0000, 0x4010_0000),
+    ];
+}
+
+/// Kinetis L (KL0x, KL1x, KL2x, ...): SRAM_U and the AIPS peripheral bridge
+///
+/// The GPIO block at 0x400F_F000 is not reachable through the BME on these parts.
+pub struct KinetisL;
+
+impl BmeTarget for KinetisL {
+    const REGIONS: &'static [AddressRange] = &[
+        AddressRange::new(0x2000_0000, 0x2010_0000),
+        AddressRange::new(0x4000_0000, 0x4008_0000),
+    ];
+}
+
+/// Kinetis E (KE0x): SRAM_U, the AIPS peripheral bridge and the GPIO block
+pub struct KinetisE;
+
+impl BmeTarget for KinetisE {
+    const REGIONS: &'static [AddressRange] = &[
+        AddressRange::new(0x2000_0000, 0x2010_0000),
+        AddressRange::new(0x4000_0000, 0x4008_0000),
+        AddressRange::new(0x400f_f000, 0x4010_0000),
+    ];
+}
+
+/// Kinetis V (KV1x): the BME covers the same ranges as on the Kinetis L families
+pub type KinetisV = KinetisL;
+
+/// Kinetis M (KM1x, KM3x): the BME covers the same ranges as on the Kinetis L families
+pub type KinetisM = KinetisL;
+
+select_target! {
+    /// The target used by the BME operations of [`VolatileCell`](../struct.VolatileCell.html) and
+    /// friends
+    default Kinetis,
+    "bme-kinetis-l" => KinetisL,
+    "bme-kinetis-v" => KinetisV,
+    "bme-kinetis-m" => KinetisM,
+    "bme-kinetis-e" => KinetisE
+}
+
+/// An operation that the BME performs on a decorated access
+///
+/// The store operations (`And`, `Or`, `Xor` and `SetField`) combine the value written to the
+/// decorated address with the current contents of the target; the load operations return (part
+/// of) the target and may modify it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BmeOperation {
+    /// Store: ANDs the written value into the target
+    And,
+    /// Store: ORs the written value into the target
+    Or,
+    /// Store: XORs the written value into the target
+    Xor,
+    /// Store: inserts the bits of the written value selected by the field into the target (BFI)
+    SetField {
+        /// The lowest bit of the field
+        first_bit: u8,
+        /// The width of the field, from 1 to 16 bits
+        bit_count: u8,
+    },
+    /// Load: returns bit `bit` of the target, and clears it (LAC1)
+    LoadClearBit {
+        /// The bit to return and clear
+        bit: u8,
+    },
+    /// Load: returns bit `bit` of the target, and sets it (LAS1)
+    LoadSetBit {
+        /// The bit to return and set
+        bit: u8,
+    },
+    /// Load: returns the field of the target, shifted down to bit 0 (UBFX)
+    GetField {
+        /// The lowest bit of the field
+        first_bit: u8,
+        /// The width of the field, from 1 to 16 bits
+        bit_count: u8,
+    },
+}
+impl BmeOperation {
+    #[inline(always)]
+    pub(crate) const fn try_bits(&self) -> Result<u32, Error> {
+        match self {
+            BmeOperation::And => Ok(0x04000000),
+            BmeOperation::Or => Ok(0x08000000),
+            BmeOperation::Xor => Ok(0x0c000000),
+            BmeOperation::SetField{first_bit, bit_count} |
+            BmeOperation::GetField{first_bit, bit_count} => {
+                if *bit_count == 0 || *bit_count > 16 || *first_bit > 31 {
+                    return Err(Error::BadFieldWidth);
+                }
+                Ok(Self::field_bits(*first_bit, *bit_count))
+            },
+            BmeOperation::LoadClearBit{bit} |
+            BmeOperation::LoadSetBit{bit} => {
+                if *bit > 31 {
+                    return Err(Error::BitOutOfRange);
+                }
+                let op_bits = match self {
+                    BmeOperation::LoadClearBit{bit: _} => 0x08000000,
+                    _ => 0x0c000000,
+                };
+                Ok(op_bits | (((*bit & 0x1f) as u32) << 21))
+            },
+        }
+    }
+    /// Encodes an already-validated BFI or UBFX field
+    #[inline(always)]
+    pub(crate) const fn field_bits(first_bit: u8, bit_count: u8) -> u32 {
+        0x10000000 |
+            (((first_bit & 0x1f) as u32) << 23) |
+            ((((bit_count-1) & 0xf) as u32) << 19)
+    }
+    #[inline(always)]
+    pub(crate) fn bits(&self) -> u32 {
+        match self.try_bits() {
+            Ok(bits) => bits,
+            Err(Error::BitOutOfRange) => panic!("{:?} out of range; bit must be <32", self),
+            Err(_) => panic!("{:?} out of range; bit_count must be between 1 and 16 inclusive and first_bit must be <32", self),
+        }
+    }
+    /// Returns the address bits that this operation's decoration occupies
+    ///
+    /// The BME rebuilds the target address from the bits outside of these, so they must be
+    /// clear in the address being decorated.
+    #[inline(always)]
+    const fn decoration_mask(&self) -> u32 {
+        match self {
+            BmeOperation::SetField{first_bit: _, bit_count: _} |
+            BmeOperation::GetField{first_bit: _, bit_count: _} => 0x1ff80000,
+            _ => 0x1ff00000,
+        }
+    }
+    /// Returns whether this operation can decorate `addr` on the target `B`
+    ///
+    /// This is a `const fn` so that the check can be evaluated at compile time for addresses
+    /// that are known up front, as in [`Reg`](../struct.Reg.html).
+    #[inline(always)]
+    pub(crate) const fn supports_on<B: BmeTarget>(&self, addr: PhysAddr32) -> bool {
+        if addr.get() & self.decoration_mask() != 0 {
+            return false;
+        }
+        let mut i = 0;
+        while i < B::REGIONS.len() {
+            if B::REGIONS[i].contains(addr) {
+                return true;
+            }
+            i += 1;
+        }
+        false
+    }
+    /// Returns whether this operation can decorate `addr` on the [`Selected`](type.Selected.html)
+    /// target
+    #[inline(always)]
+    pub(crate) const fn supports(&self, addr: PhysAddr32) -> bool {
+        self.supports_on::<Selected>(addr)
+    }
+    /// Checks the operation against the size in bytes of the access it decorates
+    ///
+    /// The decoration is encoded the same way for every access size, but the BME only decorates
+    /// byte, halfword and word accesses, and a field or bit has to lie within the accessed
+    /// value: for a byte access a field can start at bits 0-7 and be up to 8 bits wide, for a
+    /// halfword at bits 0-15 and up to 16 bits wide, and for a word at bits 0-31 and up to 16
+    /// bits wide.
+    #[inline(always)]
+    pub(crate) const fn check_size(&self, size: usize) -> Result<(), Error> {
+        if size != 1 && size != 2 && size != 4 {
+            return Err(Error::UnsupportedSize);
+        }
+        let value_bits = size * 8;
+        match self {
+            BmeOperation::SetField{first_bit, bit_count} |
+            BmeOperation::GetField{first_bit, bit_count}
+                if *first_bit as usize + *bit_count as usize > value_bits => Err(Error::BadFieldWidth),
+            BmeOperation::LoadClearBit{bit} |
+            BmeOperation::LoadSetBit{bit}
+                if *bit as usize >= value_bits => Err(Error::BitOutOfRange),
+            _ => Ok(()),
+        }
+    }
+    #[inline(always)]
+    pub(crate) fn try_wrap_pointer<T>(&self, ptr: *mut T) -> Result<*mut T, Error> {
+        let bits = self.try_bits()?;
+        self.check_size(core::mem::size_of::<T>())?;
+        self.try_decorate(ptr, bits)
+    }
+    /// Like [`try_wrap_pointer`](#method.try_wrap_pointer), with the operation's bits computed
+    /// up front
+    #[inline(always)]
+    pub(crate) fn try_decorate<T>(&self, ptr: *mut T, bits: u32) -> Result<*mut T, Error> {
+        let addr = PhysAddr32::try_from_ptr(ptr)?;
+        if !self.supports(addr) {
+            return Err(Error::AddressOutOfRegion);
+        }
+        Ok(PhysAddr32::new(addr.get() | bits).to_ptr())
+    }
+    #[inline(always)]
+    pub(crate) fn wrap_pointer<T>(&self, ptr: *mut T) -> *mut T {
+        let bits = self.bits();
+        if let Err(e) = self.check_size(core::mem::size_of::<T>()) {
+            panic!("Tried to use BME operation {:?} on a {}-byte value: {}", self, core::mem::size_of::<T>(), e);
+        }
+        self.decorate(ptr, bits)
+    }
+    /// Like [`wrap_pointer`](#method.wrap_pointer), with the operation's bits computed up front
+    #[inline(always)]
+    pub(crate) fn decorate<T>(&self, ptr: *mut T, bits: u32) -> *mut T {
+        match PhysAddr32::try_from_ptr(ptr) {
+            Ok(addr) if self.supports(addr) => PhysAddr32::new(addr.get() | bits).to_ptr(),
+            _ => panic!("Tried to use BME on address {:p}, which operation {:?} does not support", ptr, self),
+        }
+    }
+}
+
+/// A BFI field whose position is known at compile time
+///
+/// [`BITS`](#associatedconstant.BITS) is the field's decoration bits, or the reason the BME
+/// cannot set the field of a `T`.
+pub(crate) struct BmeField<T, const FIRST_BIT: u8, const BIT_COUNT: u8>(core::marker::PhantomData<T>);
+impl<T, const FIRST_BIT: u8, const BIT_COUNT: u8> BmeField<T, FIRST_BIT, BIT_COUNT> {
+    pub(crate) const BITS: Result<u32, Error> = match Self::OP.check_size(core::mem::size_of::<T>()) {
+        Ok(()) => Self::OP.try_bits(),
+        Err(e) => Err(e),
+    };
+    pub(crate) const OP: BmeOperation = BmeOperation::SetField{first_bit: FIRST_BIT, bit_count: BIT_COUNT};
+}
+
+/// Returns the address `addr` of a 32-bit value decorated for `op`, on the
+/// [`Selected`](type.Selected.html) target
+#[inline(always)]
+pub const fn decorate(op: BmeOperation, addr: PhysAddr32) -> Result<PhysAddr32, Error> {
+    decorate_on::<Selected>(op, addr)
+}
+
+/// Like [`decorate`](fn.decorate.html), on the target `B`
+#[inline(always)]
+pub const fn decorate_on<B: BmeTarget>(op: BmeOperation, addr: PhysAddr32)
+    -> Result<PhysAddr32, Error>
+{
+    decorate_sized_on::<B>(op, addr, 4)
+}
+
+/// Returns the address `addr` of a `size`-byte value decorated for `op` on the target `B`
+#[inline(always)]
+pub(crate) const fn decorate_sized_on<B: BmeTarget>(op: BmeOperation, addr: PhysAddr32, size: usize)
+    -> Result<PhysAddr32, Error>
+{
+    let bits = match op.try_bits() {
+        Ok(bits) => bits,
+        Err(e) => return Err(e),
+    };
+    if let Err(e) = op.check_size(size) {
+        return Err(e);
+    }
+    if addr.get() & (size as u32 - 1) != 0 {
+        return Err(Error::Misaligned);
+    }
+    if !op.supports_on::<B>(addr) {
+        return Err(Error::AddressOutOfRegion);
+    }
+    Ok(PhysAddr32::new(addr.get() | bits))
+}
+
+/// The kind of access made to a decorated address
+///
+/// The BME encodes load and store operations with the same address bits, so decoding an address
+/// requires knowing which kind of access was made to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BmeAccess {
+    /// A load from the decorated address
+    Load,
+    /// A store to the decorated address
+    Store,
+}
+
+/// Returns the operation and the source address that `addr` is decorated with, on the
+/// [`Selected`](type.Selected.html) target
+#[inline]
+pub fn decode(addr: PhysAddr32, access: BmeAccess) -> Result<(BmeOperation, PhysAddr32), Error> {
+    decode_on::<Selected>(addr, access)
+}
+
+/// Like [`decode`](fn.decode.html), on the target `B`
+pub fn decode_on<B: BmeTarget>(addr: PhysAddr32, access: BmeAccess)
+    -> Result<(BmeOperation, PhysAddr32), Error>
+{
+    let addr = addr.get();
+    let bit = ((addr >> 21) & 0x1f) as u8;
+    let first_bit = ((addr >> 23) & 0x1f) as u8;
+    let bit_count = ((addr >> 19) & 0xf) as u8 + 1;
+    let op = match (access, (addr >> 26) & 0x7) {
+        (_, 0) => return Err(Error::AddressOutOfRegion),
+        (BmeAccess::Store, 1) => BmeOperation::And,
+        (BmeAccess::Store, 2) => BmeOperation::Or,
+        (BmeAccess::Store, 3) => BmeOperation::Xor,
+        (BmeAccess::Store, _) => BmeOperation::SetField{first_bit, bit_count},
+        (BmeAccess::Load, 2) => BmeOperation::LoadClearBit{bit},
+        (BmeAccess::Load, 3) => BmeOperation::LoadSetBit{bit},
+        (BmeAccess::Load, 1) => return Err(Error::AddressOutOfRegion),
+        (BmeAccess::Load, _) => BmeOperation::GetField{first_bit, bit_count},
+    };
+    // The reserved bits of the decoration, which the operation does not encode, must be clear
+    if op.try_bits() != Ok(addr & op.decoration_mask()) {
+        return Err(Error::AddressOutOfRegion);
+    }
+    let source = PhysAddr32::new(addr & !op.decoration_mask());
+    if !op.supports_on::<B>(source) {
+        return Err(Error::AddressOutOfRegion);
+    }
+    Ok((op, source))
+}
+
+/// The decorated address of a `VolatileCell` for one BME operation
+///
+/// Only accesses of the size of `T` are decorated by the BME.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Decorated<T> {
+    addr: PhysAddr32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Decorated<T> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Decorated<T> {}
+
+impl<T> Decorated<T> {
+    /// Decorates the address of `cell` for `op`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation or the address is not supported; see [`try_new`](#method.try_new).
+    #[inline(always)]
+    pub fn new(cell: &VolatileCell<T>, op: BmeOperation) -> Self {
+        match Self::try_new(cell, op) {
+            Ok(decorated) => decorated,
+            Err(e) => panic!("Tried to decorate address {:p} for {:?}: {}", cell.as_ptr(), op, e),
+        }
+    }
+
+    /// Like [`new`](#method.new), but returns an error instead of panicking if the operation or
+    /// the address is not supported.
+    #[inline(always)]
+    pub fn try_new(cell: &VolatileCell<T>, op: BmeOperation) -> Result<Self, Error> {
+        Self::try_from_addr(PhysAddr32::try_from_ptr(cell.as_ptr())?, op)
+    }
+
+    /// Decorates `addr`, the address of a `T`, for `op`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation or the address is not supported; see
+    /// [`try_from_addr`](#method.try_from_addr).
+    #[inline(always)]
+    pub const fn from_addr(addr: PhysAddr32, op: BmeOperation) -> Self {
+        match Self::try_from_addr(addr, op) {
+            Ok(decorated) => decorated,
+            Err(_) => panic!("Tried to decorate an address or operation the BME does not support"),
+        }
+    }
+
+    /// Like [`from_addr`](#method.from_addr), but returns an error instead of panicking if the
+    /// operation or the address is not supported.
+    #[inline(always)]
+    pub const fn try_from_addr(addr: PhysAddr32, op: BmeOperation) -> Result<Self, Error> {
+        match decorate_sized_on::<Selected>(op, addr, mem::size_of::<T>()) {
+            Ok(addr) => Ok(Decorated { addr, _marker: PhantomData }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the decorated address
+    #[inline(always)]
+    pub const fn addr(&self) -> PhysAddr32 {
+        self.addr
+    }
+
+    /// Returns the decorated address as a pointer
+    ///
+    /// The CPU can access it too, with a volatile store for the store operations and a volatile
+    /// load for the load operations.
+    #[inline(always)]
+    pub fn as_ptr(&self) -> *mut T {
+        self.addr.to_ptr()
+    }
+}
+
+#[cfg(test)]
+mod test_bme {
+    use super::*;
+
+    #[test]
+    fn test_set_field_bits() {
+        for first_bit in 0..32 {
+            for bit_count in 1..=16 {
+                let op = BmeOperation::SetField{first_bit, bit_count};
+                let val = op.bits() & 0xf007ffff;
+                assert_eq!(val, 0x10000000, "0x{:X} != 0x10000000 with first_bit={} bit_count={}", val, first_bit, bit_count);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_set_field_zero_bits() {
+        let op = BmeOperation::SetField{first_bit: 0, bit_count: 0};
+        op.bits();
+    }
+    #[test]
+    #[should_panic]
+    fn test_set_field_too_many_bits() {
+        let op = BmeOperation::SetField{first_bit: 0, bit_count: 42};
+        op.bits();
+    }
+    #[test]
+    #[should_panic]
+    fn test_set_field_wrong_first_bit() {
+        let op = BmeOperation::SetField{first_bit: 32, bit_count: 0};
+        op.bits();
+    }
+
+    #[test]
+    fn test_try_bits() {
+        assert_eq!(BmeOperation::SetField{first_bit: 0, bit_count: 0}.try_bits(), Err(Error::BadFieldWidth));
+        assert_eq!(BmeOperation::SetField{first_bit: 0, bit_count: 17}.try_bits(), Err(Error::BadFieldWidth));
+        assert_eq!(BmeOperation::SetField{first_bit: 32, bit_count: 1}.try_bits(), Err(Error::BadFieldWidth));
+        assert_eq!(BmeOperation::Or.try_bits(), Ok(0x08000000));
+    }
+
+    #[test]
+    fn test_load_bits() {
+        assert_eq!(BmeOperation::LoadClearBit{bit: 0}.bits(), 0x08000000);
+        assert_eq!(BmeOperation::LoadSetBit{bit: 31}.bits(), 0x0fe00000);
+        assert_eq!(BmeOperation::LoadSetBit{bit: 32}.try_bits(), Err(Error::BitOutOfRange));
+        for first_bit in 0..32 {
+            for bit_count in 1..=16 {
+                let ubfx = BmeOperation::GetField{first_bit, bit_count};
+                let bfi = BmeOperation::SetField{first_bit, bit_count};
+                assert_eq!(ubfx.bits(), bfi.bits());
+            }
+        }
+        let op = BmeOperation::GetField{first_bit: 0, bit_count: 1};
+        assert_eq!(op.try_wrap_pointer(0x40080000usize as *mut u32), Err(Error::AddressOutOfRegion));
+    }
+
+    #[test]
+    fn test_const_field_bits() {
+        assert_eq!(BmeField::<u32, 0, 1>::BITS, Ok(BmeOperation::SetField{first_bit: 0, bit_count: 1}.bits()));
+        assert_eq!(BmeField::<u32, 16, 16>::BITS, Ok(BmeOperation::SetField{first_bit: 16, bit_count: 16}.bits()));
+        assert_eq!(BmeField::<u8, 4, 4>::BITS, Ok(BmeOperation::SetField{first_bit: 4, bit_count: 4}.bits()));
+        assert_eq!(BmeField::<u32, 0, 32>::BITS, Err(Error::BadFieldWidth));
+        assert_eq!(BmeField::<u64, 0, 8>::BITS, Err(Error::UnsupportedSize));
+    }
+
+    #[test]
+    fn test_try_wrap_pointer() {
+        assert_eq!(BmeOperation::Or.try_wrap_pointer(0x40001000usize as *mut u32),
+                   Ok(0x48001000usize as *mut u32));
+        assert_eq!(BmeOperation::Or.try_wrap_pointer(0x40100000usize as *mut u32),
+                   Err(Error::AddressOutOfRegion));
+        let op = BmeOperation::SetField{first_bit: 0, bit_count: 1};
+        assert_eq!(op.try_wrap_pointer(0x40080000usize as *mut u32), Err(Error::AddressOutOfRegion));
+    }
+
+    #[test]
+    fn test_targets() {
+        let op = BmeOperation::Or;
+        assert!(op.supports_on::<Kinetis>(PhysAddr32::new(0x400ff000)));
+        assert!(!op.supports_on::<KinetisL>(PhysAddr32::new(0x400ff000)));
+        assert!(op.supports_on::<KinetisE>(PhysAddr32::new(0x400ff000)));
+        assert!(op.supports_on::<KinetisL>(PhysAddr32::new(0x4007fffc)));
+        assert!(!op.supports_on::<KinetisL>(PhysAddr32::new(0x40080000)));
+        let field = BmeOperation::SetField{first_bit: 0, bit_count: 1};
+        assert!(!field.supports_on::<KinetisE>(PhysAddr32::new(0x400ff000)));
+        assert!(field.supports_on::<KinetisE>(PhysAddr32::new(0x20000000)));
+        assert!(!op.supports_on::<Kinetis>(PhysAddr32::new(0x00000000)));
+        assert!(!op.supports_on::<Kinetis>(PhysAddr32::new(0x60000000)));
+    }
+
+    #[test]
+    fn test_access_sizes() {
+        let addr = 0x40001000usize;
+        // Byte accesses: fields within bits 0-7
+        let op = BmeOperation::SetField{first_bit: 0, bit_count: 8};
+        assert!(op.try_wrap_pointer(addr as *mut u8).is_ok());
+        let op = BmeOperation::SetField{first_bit: 6, bit_count: 5};
+        assert_eq!(op.try_wrap_pointer(addr as *mut u8), Err(Error::BadFieldWidth));
+        let op = BmeOperation::GetField{first_bit: 8, bit_count: 1};
+        assert_eq!(op.try_wrap_pointer(addr as *mut u8), Err(Error::BadFieldWidth));
+        assert_eq!(BmeOperation::LoadSetBit{bit: 8}.try_wrap_pointer(addr as *mut u8),
+                   Err(Error::BitOutOfRange));
+        assert!(BmeOperation::LoadSetBit{bit: 7}.try_wrap_pointer(addr as *mut u8).is_ok());
+        // Halfword accesses: fields within bits 0-15
+        let op = BmeOperation::SetField{first_bit: 0, bit_count: 16};
+        assert!(op.try_wrap_pointer(addr as *mut u16).is_ok());
+        let op = BmeOperation::SetField{first_bit: 12, bit_count: 5};
+        assert_eq!(op.try_wrap_pointer(addr as *mut u16), Err(Error::BadFieldWidth));
+        assert_eq!(BmeOperation::LoadClearBit{bit: 16}.try_wrap_pointer(addr as *mut u16),
+                   Err(Error::BitOutOfRange));
+        // Word accesses: fields within bits 0-31, at most 16 bits wide
+        let op = BmeOperation::SetField{first_bit: 16, bit_count: 16};
+        assert!(op.try_wrap_pointer(addr as *mut u32).is_ok());
+        let op = BmeOperation::SetField{first_bit: 20, bit_count: 16};
+        assert_eq!(op.try_wrap_pointer(addr as *mut u32), Err(Error::BadFieldWidth));
+        assert!(BmeOperation::LoadClearBit{bit: 31}.try_wrap_pointer(addr as *mut u32).is_ok());
+        // Other sizes cannot be decorated at all
+        assert_eq!(BmeOperation::Or.try_wrap_pointer(addr as *mut u64), Err(Error::UnsupportedSize));
+        assert_eq!(BmeOperation::Or.try_wrap_pointer(addr as *mut ()), Err(Error::UnsupportedSize));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_field_too_wide_for_byte() {
+        BmeOperation::SetField{first_bit: 6, bit_count: 5}.wrap_pointer(0x40001000usize as *mut u8);
+    }
+
+    #[test]
+    fn test_decorated() {
+        let gpio = PhysAddr32::new(0x4000_1000);
+        let or = Decorated::<u32>::from_addr(gpio, BmeOperation::Or);
+        assert_eq!(or.addr(), PhysAddr32::new(0x4800_1000));
+        assert_eq!(or.as_ptr(), 0x4800_1000usize as *mut u32);
+        let field = BmeOperation::SetField{first_bit: 0, bit_count: 0};
+        assert_eq!(Decorated::<u32>::try_from_addr(gpio, field), Err(Error::BadFieldWidth));
+        assert_eq!(Decorated::<u32>::try_from_addr(PhysAddr32::new(0x4010_0000), BmeOperation::Or),
+                   Err(Error::AddressOutOfRegion));
+        assert_eq!(Decorated::<u8>::try_from_addr(gpio, BmeOperation::LoadSetBit{bit: 8}),
+                   Err(Error::BitOutOfRange));
+        assert_eq!(Decorated::<u64>::try_from_addr(gpio, BmeOperation::Or),
+                   Err(Error::UnsupportedSize));
+        let halfword = PhysAddr32::new(0x4000_1002);
+        assert_eq!(Decorated::<u16>::try_from_addr(halfword, BmeOperation::Or).map(|d| d.addr()),
+                   Ok(PhysAddr32::new(0x4800_1002)));
+        assert_eq!(Decorated::<u32>::try_from_addr(halfword, BmeOperation::Or), Err(Error::Misaligned));
+        let cell = VolatileCell::new(0u32);
+        assert_eq!(Decorated::try_new(&cell, BmeOperation::Or), Err(Error::AddressOutOfRegion));
+    }
+
+    #[test]
+    fn test_decorated_is_shareable() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Decorated<u32>>();
+        static SET_PIN: Decorated<u32> =
+            Decorated::from_addr(PhysAddr32::new(0x4000_1000), BmeOperation::Or);
+        assert_eq!(SET_PIN.addr(), PhysAddr32::new(0x4800_1000));
+    }
+
+    #[test]
+    fn test_decode() {
+        let ops = [
+            (BmeOperation::And, BmeAccess::Store),
+            (BmeOperation::Or, BmeAccess::Store),
+            (BmeOperation::Xor, BmeAccess::Store),
+            (BmeOperation::SetField{first_bit: 12, bit_count: 3}, BmeAccess::Store),
+            (BmeOperation::LoadClearBit{bit: 7}, BmeAccess::Load),
+            (BmeOperation::LoadSetBit{bit: 31}, BmeAccess::Load),
+            (BmeOperation::GetField{first_bit: 31, bit_count: 1}, BmeAccess::Load),
+        ];
+        let source = PhysAddr32::new(0x4007_f008);
+        for &(op, access) in &ops {
+            let decorated = PhysAddr32::try_from_ptr(op.wrap_pointer(source.to_ptr::<u32>())).unwrap();
+            assert_eq!(decode(decorated, access), Ok((op, source)));
+        }
+        assert_eq!(decode(source, BmeAccess::Store), Err(Error::AddressOutOfRegion));
+        assert_eq!(decode(PhysAddr32::new(0x4400_1000), BmeAccess::Load), Err(Error::AddressOutOfRegion));
+        // Reserved bits: 25:20 for AND, OR and XOR, and 20 for LAC1 and LAS1
+        assert_eq!(decode(PhysAddr32::new(0x4410_0000), BmeAccess::Store), Err(Error::AddressOutOfRegion));
+        assert_eq!(decode(PhysAddr32::new(0x4a00_0000), BmeAccess::Store), Err(Error::AddressOutOfRegion));
+        assert_eq!(decode(PhysAddr32::new(0x4c10_0000), BmeAccess::Load), Err(Error::AddressOutOfRegion));
+        assert_eq!(decode_on::<KinetisL>(PhysAddr32::new(0x480f_f000), BmeAccess::Store),
+                   Err(Error::AddressOutOfRegion));
+    }
+
+    #[test]
+    fn test_const_decorate() {
+        const GPIO: PhysAddr32 = PhysAddr32::new(0x400f_f004);
+        const SET_PIN: PhysAddr32 = match decorate_on::<Kinetis>(BmeOperation::Or, GPIO) {
+            Ok(addr) => addr,
+            Err(_) => panic!(),
+        };
+        assert_eq!(SET_PIN, PhysAddr32::new(0x480f_f004));
+        let field = BmeOperation::SetField{first_bit: 30, bit_count: 4};
+        assert_eq!(decorate_on::<Kinetis>(field, PhysAddr32::new(0x4000_0000)), Err(Error::BadFieldWidth));
+        assert_eq!(decorate_on::<Kinetis>(BmeOperation::Or, PhysAddr32::new(0x400f_f006)),
+                   Err(Error::Misaligned));
+        assert_eq!(decorate_on::<KinetisL>(BmeOperation::Or, GPIO), Err(Error::AddressOutOfRegion));
+    }
+}
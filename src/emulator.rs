@@ -0,0 +1,328 @@
+//! Host-side models of the hardware engines, for testing
+//!
+//! A decorated or alias address cannot be dereferenced on a host, so these models apply the
+//! accesses to a simulated [`Memory`] instead: tests can replay the addresses that the crate
+//! (or a driver) computes and assert on the resulting register values.
+//!
+//! This module is only built for the crate's own tests and with the `emulator` Cargo feature.
+//!
+//! [`Memory`]: struct.Memory.html
+
+#[cfg(any(feature = "bit-manipulation", feature = "bit-banding"))]
+use core::fmt;
+#[cfg(any(feature = "bit-manipulation", feature = "bit-banding"))]
+use core::marker::PhantomData;
+
+#[cfg(feature = "bit-banding")]
+use bitband::{self, BitBandTarget};
+#[cfg(feature = "bit-manipulation")]
+use bme::{self, BmeAccess, BmeTarget};
+#[cfg(feature = "bit-manipulation")]
+use {BmeOperation, Word};
+use PhysAddr32;
+
+/// A value that can be loaded from or stored to a [`Memory`](struct.Memory.html)
+pub trait Access: Copy {
+    /// The size of the access in bytes
+    const SIZE: usize;
+
+    /// Zero-extends the value
+    fn to_u32(self) -> u32;
+
+    /// Truncates `value` to the size of the access
+    fn from_u32(value: u32) -> Self;
+}
+
+macro_rules! access {
+    ($($t:ty),*) => {
+        $(
+            impl Access for $t {
+                const SIZE: usize = core::mem::size_of::<$t>();
+
+                #[inline(always)]
+                fn to_u32(self) -> u32 {
+                    u32::from(self)
+                }
+
+                #[inline(always)]
+                fn from_u32(value: u32) -> Self {
+                    value as $t
+                }
+            }
+        )*
+    }
+}
+
+access!(u8, u16, u32);
+
+/// Little-endian simulated memory covering `bytes.len()` bytes from `base`
+///
+/// Accesses outside of the memory panic.
+#[derive(Debug)]
+pub struct Memory<'a> {
+    base: PhysAddr32,
+    bytes: &'a mut [u8],
+}
+
+impl<'a> Memory<'a> {
+    /// Creates a memory whose first byte is at address `base`
+    pub fn new(base: impl Into<PhysAddr32>, bytes: &'a mut [u8]) -> Self {
+        Memory { base: base.into(), bytes }
+    }
+
+    fn range<T: Access>(&self, addr: PhysAddr32) -> core::ops::Range<usize> {
+        match addr.get().checked_sub(self.base.get()).map(|start| start as usize) {
+            Some(start) if start + T::SIZE <= self.bytes.len() => start..start + T::SIZE,
+            _ => panic!("Tried to access address {:?} outside of the simulated memory", addr),
+        }
+    }
+
+    /// Loads the value at `addr`
+    pub fn load<T: Access>(&self, addr: impl Into<PhysAddr32>) -> T {
+        let range = self.range::<T>(addr.into());
+        let value = self.bytes[range].iter().rev().fold(0, |v, &b| v << 8 | u32::from(b));
+        T::from_u32(value)
+    }
+
+    /// Stores `value` at `addr`
+    pub fn store<T: Access>(&mut self, addr: impl Into<PhysAddr32>, value: T) {
+        let range = self.range::<T>(addr.into());
+        let mut value = value.to_u32();
+        for b in &mut self.bytes[range] {
+            *b = value as u8;
+            value >>= 8;
+        }
+    }
+}
+
+/// A model of the Kinetis BME in front of a [`Memory`](struct.Memory.html)
+///
+/// Accesses to decorated addresses are decoded for the target `B` and applied to the memory the
+/// way the BME does; accesses to any other address go to the memory unchanged.
+#[cfg(feature = "bit-manipulation")]
+pub struct BmeEmulator<'a, B: BmeTarget = bme::Selected> {
+    memory: Memory<'a>,
+    _target: PhantomData<B>,
+}
+
+// Not derived, which would require the target to implement `Debug` too
+#[cfg(feature = "bit-manipulation")]
+impl<'a, B: BmeTarget> fmt::Debug for BmeEmulator<'a, B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BmeEmulator").field("memory", &self.memory).finish()
+    }
+}
+
+#[cfg(feature = "bit-manipulation")]
+impl<'a, B: BmeTarget> BmeEmulator<'a, B> {
+    /// Puts the BME in front of `memory`
+    pub fn new(memory: Memory<'a>) -> Self {
+        BmeEmulator { memory, _target: PhantomData }
+    }
+
+    /// Returns the memory behind the BME
+    pub fn memory(&mut self) -> &mut Memory<'a> {
+        &mut self.memory
+    }
+
+    /// Loads from `addr`, performing the decorated load it encodes if any
+    pub fn load<T: Access>(&mut self, addr: impl Into<PhysAddr32>) -> T {
+        let addr = addr.into();
+        let (op, source) = match bme::decode_on::<B>(addr, BmeAccess::Load) {
+            Ok(decoded) => decoded,
+            Err(_) => return self.memory.load(addr),
+        };
+        let old = self.memory.load::<T>(source).to_u32();
+        let (result, new) = match op {
+            BmeOperation::LoadClearBit{bit} => ((old >> bit) & 1, old & !u32::bit(bit)),
+            BmeOperation::LoadSetBit{bit} => ((old >> bit) & 1, old | u32::bit(bit)),
+            BmeOperation::GetField{first_bit, bit_count} => {
+                ((old & u32::field_mask(first_bit, bit_count)) >> first_bit, old)
+            },
+            _ => unreachable!(),
+        };
+        if new != old {
+            self.memory.store(source, T::from_u32(new));
+        }
+        T::from_u32(result)
+    }
+
+    /// Stores `value` to `addr`, performing the decorated store it encodes if any
+    pub fn store<T: Access>(&mut self, addr: impl Into<PhysAddr32>, value: T) {
+        let addr = addr.into();
+        let (op, source) = match bme::decode_on::<B>(addr, BmeAccess::Store) {
+            Ok(decoded) => decoded,
+            Err(_) => return self.memory.store(addr, value),
+        };
+        let old = self.memory.load::<T>(source).to_u32();
+        let value = value.to_u32();
+        let new = match op {
+            BmeOperation::And => old & value,
+            BmeOperation::Or => old | value,
+            BmeOperation::Xor => old ^ value,
+            BmeOperation::SetField{first_bit, bit_count} => {
+                let mask = u32::field_mask(first_bit, bit_count);
+                (old & !mask) | (value & mask)
+            },
+            _ => unreachable!(),
+        };
+        self.memory.store(source, T::from_u32(new));
+    }
+}
+
+/// A model of the Cortex-M bit-band alias regions in front of a [`Memory`](struct.Memory.html)
+///
+/// Accesses to alias words of the target `B` read or write the single bit they stand for in the
+/// memory: a store changes only that bit, according to bit 0 of the stored value, and a load
+/// returns 0 or 1. Accesses to any other address go to the memory unchanged.
+#[cfg(feature = "bit-banding")]
+pub struct BitBandEmulator<'a, B: BitBandTarget = bitband::Selected> {
+    memory: Memory<'a>,
+    _target: PhantomData<B>,
+}
+
+#[cfg(feature = "bit-banding")]
+impl<'a, B: BitBandTarget> fmt::Debug for BitBandEmulator<'a, B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BitBandEmulator").field("memory", &self.memory).finish()
+    }
+}
+
+#[cfg(feature = "bit-banding")]
+impl<'a, B: BitBandTarget> BitBandEmulator<'a, B> {
+    /// Puts the alias regions in front of `memory`
+    pub fn new(memory: Memory<'a>) -> Self {
+        BitBandEmulator { memory, _target: PhantomData }
+    }
+
+    /// Returns the memory behind the alias regions
+    pub fn memory(&mut self) -> &mut Memory<'a> {
+        &mut self.memory
+    }
+
+    /// Loads from `addr`, reading a single bit if it is an alias word
+    pub fn load<T: Access>(&mut self, addr: impl Into<PhysAddr32>) -> T {
+        let addr = addr.into();
+        match bitband::decode_on::<B>(addr) {
+            Ok((word, bit)) => T::from_u32((self.memory.load::<u32>(word) >> bit) & 1),
+            Err(_) => self.memory.load(addr),
+        }
+    }
+
+    /// Stores to `addr`, changing a single bit if it is an alias word
+    pub fn store<T: Access>(&mut self, addr: impl Into<PhysAddr32>, value: T) {
+        let addr = addr.into();
+        match bitband::decode_on::<B>(addr) {
+            Ok((word, bit)) => {
+                let old = self.memory.load::<u32>(word);
+                let new = if value.to_u32() & 1 == 1 { old | 1 << bit } else { old & !(1 << bit) };
+                self.memory.store(word, new)
+            },
+            Err(_) => self.memory.store(addr, value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_emulator {
+    use super::*;
+
+    #[test]
+    fn test_memory() {
+        let mut bytes = [0; 8];
+        let mut memory = Memory::new(0x2000_0000, &mut bytes);
+        memory.store(0x2000_0004, 0x1234_5678u32);
+        assert_eq!(memory.load::<u16>(0x2000_0006), 0x1234);
+        assert_eq!(memory.load::<u8>(0x2000_0004), 0x78);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_memory_out_of_range() {
+        let mut bytes = [0; 8];
+        Memory::new(0x2000_0000, &mut bytes).load::<u32>(0x2000_0006);
+    }
+
+    #[cfg(feature = "bit-manipulation")]
+    #[test]
+    fn test_bme_stores() {
+        let mut bytes = [0; 4];
+        let mut bme = BmeEmulator::<bme::Kinetis>::new(Memory::new(0x4000_1000, &mut bytes));
+        let reg = PhysAddr32::new(0x4000_1000);
+        bme.store(reg, 0x0000_ff0fu32);
+        bme.store(bme::decorate(BmeOperation::Or, reg).unwrap(), 0x0000_00f0u32);
+        bme.store(bme::decorate(BmeOperation::And, reg).unwrap(), !0x0000_0f00u32);
+        bme.store(bme::decorate(BmeOperation::Xor, reg).unwrap(), 0x8000_0001u32);
+        assert_eq!(bme.memory().load::<u32>(reg), 0x8000_f0fe);
+        let bfi = BmeOperation::SetField{first_bit: 4, bit_count: 8};
+        bme.store(bme::decorate(bfi, reg).unwrap(), 0x1234_5a5au32);
+        assert_eq!(bme.memory().load::<u32>(reg), 0x8000_fa5e);
+    }
+
+    #[cfg(feature = "bit-manipulation")]
+    #[test]
+    fn test_bme_loads() {
+        let mut bytes = [0; 2];
+        let mut bme = BmeEmulator::<bme::Kinetis>::new(Memory::new(0x2000_0100, &mut bytes));
+        let reg = PhysAddr32::new(0x2000_0100);
+        bme.store(reg, 0x00a5u16);
+        let las1 = bme::decorate(BmeOperation::LoadSetBit{bit: 1}, reg).unwrap();
+        assert_eq!(bme.load::<u16>(las1), 0);
+        assert_eq!(bme.load::<u16>(las1), 1);
+        let lac1 = bme::decorate(BmeOperation::LoadClearBit{bit: 7}, reg).unwrap();
+        assert_eq!(bme.load::<u16>(lac1), 1);
+        assert_eq!(bme.memory().load::<u16>(reg), 0x0027);
+        let ubfx = bme::decorate(BmeOperation::GetField{first_bit: 1, bit_count: 3}, reg).unwrap();
+        assert_eq!(bme.load::<u16>(ubfx), 0x3);
+    }
+
+    #[cfg(feature = "bit-manipulation")]
+    #[test]
+    fn test_bme_undecorated() {
+        let mut bytes = [0; 4];
+        let mut bme = BmeEmulator::<bme::KinetisL>::new(Memory::new(0x1fff_f000, &mut bytes));
+        bme.store(0x1fff_f000, 5u32);
+        assert_eq!(bme.load::<u32>(0x1fff_f000), 5);
+        assert_eq!(bme.memory().load::<u32>(0x1fff_f000), 5);
+    }
+
+    #[cfg(any(feature = "bit-manipulation", feature = "bit-banding"))]
+    #[test]
+    fn test_debug() {
+        fn assert_debug<T: fmt::Debug>(_: &T) {}
+        let mut bytes = [0; 4];
+        #[cfg(feature = "bit-manipulation")]
+        assert_debug(&BmeEmulator::<bme::KinetisL>::new(Memory::new(0x2000_0000, &mut bytes)));
+        #[cfg(feature = "bit-banding")]
+        assert_debug(&BitBandEmulator::<bitband::CortexM>::new(Memory::new(0x2000_0000, &mut bytes)));
+    }
+
+    #[cfg(feature = "bit-banding")]
+    #[test]
+    fn test_bitband() {
+        let mut bytes = [0; 64];
+        let mut bb = BitBandEmulator::<bitband::CortexM>::new(Memory::new(0x4000_0fe0, &mut bytes));
+        for addr in (0x4000_0fe0u32..0x4000_1020).step_by(4) {
+            for bit in 0..32 {
+                let alias = bitband::alias_on::<bitband::CortexM>(PhysAddr32::new(addr), bit).unwrap();
+                bb.store(alias, 1u32);
+                assert_eq!(bb.memory().load::<u32>(addr), 1 << bit);
+                assert_eq!(bb.load::<u32>(alias), 1);
+                bb.store(alias, 0u32);
+                assert_eq!(bb.load::<u32>(alias), 0);
+            }
+        }
+        assert!(bytes.iter().all(|&b| b == 0));
+    }
+
+    #[cfg(feature = "bit-banding")]
+    #[test]
+    fn test_bitband_bytes() {
+        let mut bytes = [0xff; 4];
+        let mut bb = BitBandEmulator::<bitband::CortexM>::new(Memory::new(0x2000_0000, &mut bytes));
+        let byte = PhysAddr32::new(0x2000_0002);
+        bb.store(bitband::alias_on::<bitband::CortexM>(byte, 3).unwrap(), 0u8);
+        assert_eq!(bb.memory().load::<u32>(0x2000_0000), 0xfff7_ffff);
+        assert_eq!(bb.load::<u32>(0x2000_0000), 0xfff7_ffff);
+    }
+}
@@ -0,0 +1,36 @@
+//! Errors reported by the fallible (`try_*`) operations
+
+use core::fmt;
+
+/// The reason a `try_*` operation was rejected
+///
+/// Every operation that panics on bad input has a `try_*` variant that returns this error
+/// instead, so that firmware built without panic machinery can handle misuse itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The address is outside of the region the hardware engine supports for this operation
+    AddressOutOfRegion,
+    /// The bit number does not exist in the value
+    BitOutOfRange,
+    /// The field's first bit or bit count is not supported
+    BadFieldWidth,
+    /// The value is out of range for the operation
+    BadValue,
+    /// The hardware engine cannot operate on values of this size
+    UnsupportedSize,
+    /// The address is not aligned to the size of the value
+    Misaligned,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Error::AddressOutOfRegion => "address is outside of the supported region",
+            Error::BitOutOfRange => "bit number is out of range",
+            Error::BadFieldWidth => "field position or width is out of range",
+            Error::BadValue => "value is out of range",
+            Error::UnsupportedSize => "value size is not supported",
+            Error::Misaligned => "address is not aligned to the value size",
+        })
+    }
+}
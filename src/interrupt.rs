@@ -0,0 +1,16 @@
+//! Critical sections for the software read-modify-write fallback
+
+/// Runs `f` inside a critical section
+///
+/// This uses the [`critical-section`] crate, so the application has to link an implementation
+/// for its target, for example with the `critical-section-single-core` feature of `cortex-m`.
+/// On single-core targets that implementation masks interrupts; it is what makes the fallback
+/// read-modify-writes safe against interrupt handlers.
+///
+/// [`critical-section`]: https://docs.rs/critical-section
+#[inline(always)]
+pub(crate) fn free<F, R>(f: F) -> R
+    where F: FnOnce() -> R
+{
+    critical_section::with(|_| f())
+}
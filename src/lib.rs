@@ -2,90 +2,104 @@
 //!
 //! [`Cell`]: https://doc.rust-lang.org/std/cell/struct.Cell.html
 //! [volatile]: https://doc.rust-lang.org/std/ptr/fn.read_volatile.html
+//!
+//! Bit operations that no hardware mechanism can perform fall back to a read-modify-write inside
+//! a [`critical-section`], so the application has to provide an implementation for its target.
+//!
+//! [`critical-section`]: https://docs.rs/critical-section
 
 #![deny(missing_docs)]
 #![deny(warnings)]
-#![cfg_attr(feature = "const-fn", feature(const_fn))]
 #![no_std]
 
+extern crate critical_section;
+
 use core::cell::UnsafeCell;
 use core::ptr;
 
-#[cfg(feature = "bit-manipulation")]
-#[derive(Debug)]
-enum BmeOperation {
-    And,
-    Or,
-    Xor,
-    SetField{first_bit: u8, bit_count: u8},
+/// Declares `Selected`, the target chosen with a group of Cargo features
+///
+/// Cargo features are additive, so enabling several of them is not an error: the first one
+/// listed wins. Without any of them, `Selected` is the default target.
+#[cfg(any(feature = "bit-manipulation", feature = "bit-banding", feature = "offset-alias"))]
+macro_rules! select_target {
+    ($(#[$doc:meta])* default $default:ty, $($feature:literal => $target:ty),+) => {
+        $(#[$doc])*
+        #[cfg(not(any($(feature = $feature),+)))]
+        pub type Selected = $default;
+        select_target!(@select [$(#[$doc])*] []; $($feature => $target),+);
+    };
+    (@select [$(#[$doc:meta])*] [$($seen:literal),*];
+     $feature:literal => $target:ty $(, $next_feature:literal => $next_target:ty)*) => {
+        $(#[$doc])*
+        #[cfg(all(feature = $feature, not(any($(feature = $seen),*))))]
+        pub type Selected = $target;
+        select_target!(@select [$(#[$doc])*] [$($seen,)* $feature]; $($next_feature => $next_target),*);
+    };
+    (@select [$(#[$doc:meta])*] [$($seen:literal),*];) => {};
 }
+
+mod access;
+mod addr;
+pub mod atomic;
+#[cfg(feature = "bit-banding")]
+pub mod bitband;
+#[cfg(feature = "bit-banding")]
+mod bitmap;
 #[cfg(feature = "bit-manipulation")]
-impl BmeOperation {
-    #[inline(always)]
-    fn bits(&self) -> usize {
-        match self {
-            BmeOperation::And => 0x04000000,
-            BmeOperation::Or => 0x08000000,
-            BmeOperation::Xor => 0x0c000000,
-            BmeOperation::SetField{first_bit, bit_count} => {
-                if *bit_count == 0 || *bit_count > 16 {
-                    panic!("bit_count {} out of range; must be between 1 and 16 inclusive", *bit_count);
-                }
-                if *first_bit > 31 {
-                    panic!("first_bit {} out of range; must be <32", *first_bit);
-                }
-                0x10000000 |
-                    (usize::from(first_bit & 0x1f) << 23) |
-                    (usize::from((bit_count-1) & 0xf) << 19)
-            },
-        }
-    }
-    #[inline(always)]
-    pub fn wrap_pointer<T>(&self, ptr: *mut T) -> *mut T {
-        let addr = ptr as usize;
-        let mask = match self {
-            BmeOperation::SetField{first_bit: _, bit_count: _} => 0x6007ffff,
-            _ => 0x600fffff,
-        };
-        if addr & mask != addr {
-            panic!("Tried to use BME on address 0x{:x?}, which operation {:?} does not support", addr, self);
-        }
-        (addr | self.bits()) as *mut T
-    }
-}
+pub mod bme;
+#[cfg(any(test, feature = "emulator"))]
+pub mod emulator;
+mod error;
+mod interrupt;
+#[cfg(feature = "bit-manipulation")]
+mod lock;
+#[cfg(feature = "offset-alias")]
+pub mod offset_alias;
+mod ops;
+mod reg;
+mod setclear;
+mod volptr;
+mod word;
 
-#[cfg(all(test, feature = "bit-manipulation"))]
-mod test_bme {
-    use super::*;
+pub use access::{ReadOnly, ReadWrite, WriteOnly};
+pub use addr::PhysAddr32;
+pub use atomic::AtomicBits;
+#[cfg(feature = "bit-banding")]
+pub use bitband::{BitBandAlias, BitBandView};
+#[cfg(feature = "bit-banding")]
+pub use bitmap::BitBandBitmap;
+#[cfg(feature = "bit-manipulation")]
+pub use bme::{BmeOperation, BmeTarget, Decorated};
+#[cfg(feature = "bit-manipulation")]
+use bme::BmeField;
+pub use error::Error;
+#[cfg(feature = "bit-manipulation")]
+pub use lock::BmeLock;
+pub use reg::Reg;
+pub use setclear::{SetClearPair, SetResetRegister};
+pub use volptr::VolPtr;
+pub use word::Word;
 
-    #[test]
-    fn test_set_field_bits() {
-        for first_bit in 0..32 {
-            for bit_count in 1..=16 {
-                let op = BmeOperation::SetField{first_bit: first_bit, bit_count: bit_count};
-                let val = op.bits() & 0xf007ffff;
-                assert_eq!(val, 0x10000000, "0x{:X} != 0x10000000 with first_bit={} bit_count={}", val, first_bit, bit_count);
-            }
-        }
-    }
+/// A range of physical addresses, from `start` inclusive to `end` exclusive
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AddressRange {
+    /// The first address in the range
+    pub start: PhysAddr32,
+    /// The first address after the range
+    pub end: PhysAddr32,
+}
 
-    #[test]
-    #[should_panic]
-    fn test_set_field_zero_bits() {
-        let op = BmeOperation::SetField{first_bit: 0, bit_count: 0};
-        op.bits();
+impl AddressRange {
+    /// Creates a new `AddressRange`
+    pub const fn new(start: u32, end: u32) -> Self {
+        AddressRange { start: PhysAddr32::new(start), end: PhysAddr32::new(end) }
     }
-    #[test]
-    #[should_panic]
-    fn test_set_field_too_many_bits() {
-        let op = BmeOperation::SetField{first_bit: 0, bit_count: 42};
-        op.bits();
-    }
-    #[test]
-    #[should_panic]
-    fn test_set_field_wrong_first_bit() {
-        let op = BmeOperation::SetField{first_bit: 32, bit_count: 0};
-        op.bits();
+
+    /// Returns whether `addr` lies in the range
+    #[inline(always)]
+    pub const fn contains(&self, addr: PhysAddr32) -> bool {
+        addr.get() >= self.start.get() && addr.get() < self.end.get()
     }
 }
 
@@ -93,6 +107,7 @@ mod test_bme {
 ///
 /// [`Cell`]: https://doc.rust-lang.org/std/cell/struct.Cell.html
 /// [volatile]: https://doc.rust-lang.org/std/ptr/fn.read_volatile.html
+#[repr(transparent)]
 pub struct VolatileCell<T> {
     value: UnsafeCell<T>,
 }
@@ -110,9 +125,71 @@ impl<T> VolatileCell<T> {
     /// feature
     #[cfg(not(feature = "const-fn"))]
     pub fn new(value: T) -> Self {
+        Self::const_new(value)
+    }
+
+    /// Like `new`, but always a `const fn`, for the crate's own `const` constructors
+    #[inline(always)]
+    pub(crate) const fn const_new(value: T) -> Self {
         VolatileCell { value: UnsafeCell::new(value) }
     }
 
+    /// Treats the memory at `ptr` as a `VolatileCell`
+    ///
+    /// This is the way to map a memory-mapped register to a `VolatileCell`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be non-null and aligned for `T`, and it must point to memory (or a register)
+    /// that stays valid for reads and writes of `T` for the whole lifetime `'a`. For the
+    /// duration of `'a` the memory must only be accessed through `VolatileCell`s (or other
+    /// volatile accesses); creating a `&T` or `&mut T` to it at the same time is undefined
+    /// behavior.
+    #[inline(always)]
+    pub unsafe fn from_ptr<'a>(ptr: *mut T) -> &'a Self {
+        &*(ptr as *const Self)
+    }
+
+    /// Treats the memory at the physical address `addr` as a `VolatileCell`
+    ///
+    /// # Safety
+    ///
+    /// See [`from_ptr`]. In addition, `addr` must stay mapped for the rest of the program,
+    /// which is normally the case for the register blocks of on-chip peripherals.
+    ///
+    /// [`from_ptr`]: #method.from_ptr
+    #[inline(always)]
+    pub unsafe fn from_addr(addr: usize) -> &'static Self {
+        Self::from_ptr(addr as *mut T)
+    }
+
+    /// Returns a `&VolatileCell<T>` from a `&mut T`
+    #[inline(always)]
+    pub fn from_mut(value: &mut T) -> &Self {
+        unsafe { &*(value as *mut T as *const Self) }
+    }
+
+    /// Returns a raw pointer to the contained value
+    #[inline(always)]
+    pub fn as_ptr(&self) -> *mut T {
+        self.value.get()
+    }
+
+    /// Returns a mutable reference to the contained value
+    ///
+    /// This does not perform a volatile access; the `&mut self` borrow guarantees that no one
+    /// else is observing the value.
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.value.get() }
+    }
+
+    /// Unwraps the contained value
+    #[inline(always)]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
     /// Returns a copy of the contained value
     #[inline(always)]
     pub fn get(&self) -> T
@@ -129,112 +206,373 @@ impl<T> VolatileCell<T> {
         unsafe { ptr::write_volatile(self.value.get(), value) }
     }
 
+    /// Replaces the contained value, and returns the old contained value
+    #[inline(always)]
+    pub fn replace(&self, value: T) -> T
+        where T: Copy
+    {
+        let old = self.get();
+        self.set(value);
+        old
+    }
+
+    /// Swaps the values of two `VolatileCell`s
+    ///
+    /// Both values are read before either is written.
+    #[inline(always)]
+    pub fn swap(&self, other: &Self)
+        where T: Copy
+    {
+        if ptr::eq(self, other) {
+            return;
+        }
+        let a = self.get();
+        let b = other.get();
+        self.set(b);
+        other.set(a);
+    }
+
+    /// Takes the contained value, leaving `Default::default()` in its place
+    #[inline(always)]
+    pub fn take(&self) -> T
+        where T: Copy + Default
+    {
+        self.replace(T::default())
+    }
+
+    /// Updates the contained value using a function, like [`Cell::update`]
+    ///
+    /// This is the same read-modify-write as [`modify`].
+    ///
+    /// [`Cell::update`]: https://doc.rust-lang.org/core/cell/struct.Cell.html#method.update
+    /// [`modify`]: #method.modify
+    #[inline(always)]
+    pub fn update<F>(&self, f: F)
+        where T: Copy,
+              F: FnOnce(T) -> T
+    {
+        self.modify(f)
+    }
+
+    /// Performs a read-modify-write of the contained value
+    ///
+    /// The closure receives the value from exactly one volatile read, and its result is stored
+    /// with exactly one volatile write. The sequence is not atomic: an interrupt or another bus
+    /// master may change the value between the two accesses.
+    #[inline(always)]
+    pub fn modify<F>(&self, f: F)
+        where T: Copy,
+              F: FnOnce(T) -> T
+    {
+        self.set(f(self.get()))
+    }
+
     /// Sets a sub-field of the contained value with the bit-manipulation-engine, if enabled.
     /// See [NXP documentation] on the BME. This is a "BFI" operation.
+    /// The bits of `value` outside of the field are ignored; `value` is not shifted.
+    /// If the BME is not enabled, or cannot reach the address, this falls back to a volatile
+    /// read-modify-write inside a critical section.
     ///
     /// [NXP documentation]: https://www.nxp.com/docs/en/application-note/AN4838.pdf
     #[inline(always)]
-    #[cfg(feature = "bit-manipulation")]
     pub fn set_field(&self, first_bit: u8, bit_count: u8, value: T)
-        where T: Copy
+        where T: Word
     {
-        unsafe {
-            let op = BmeOperation::SetField{first_bit: first_bit, bit_count: bit_count};
-            let bfi_ptr = op.wrap_pointer(self.value.get());
-            ptr::write_volatile(bfi_ptr, value)
-        }
+        unsafe { ops::set_field(self.value.get(), first_bit, bit_count, value) }
+    }
+
+    /// Like [`set_field`](#method.set_field), but returns an error instead of panicking if the
+    /// field does not fit in `T`.
+    #[inline(always)]
+    pub fn try_set_field(&self, first_bit: u8, bit_count: u8, value: T) -> Result<(), Error>
+        where T: Word
+    {
+        unsafe { ops::try_set_field(self.value.get(), first_bit, bit_count, value) }
+    }
+
+    /// Sets a sub-field of the contained value with the bit-manipulation-engine, if enabled.
+    ///
+    /// Like [`set_field`](#method.set_field), but the field is given as const generic
+    /// parameters: a field that does not fit in `T` fails the build, and the decoration bits are
+    /// computed at compile time.
+    /// If the BME is not enabled, cannot reach the address or does not support the field (which
+    /// it limits to 16 bits), this falls back to a volatile read-modify-write inside a critical
+    /// section.
+    ///
+    /// ```
+    /// let c = vcell::VolatileCell::new(0u32);
+    /// c.set_field_const::<28, 4>(0xa000_0000);
+    /// assert_eq!(c.get(), 0xa000_0000);
+    /// ```
+    ///
+    /// A field that does not fit in `T` fails the build:
+    ///
+    /// ```compile_fail,E0080
+    /// let c = vcell::VolatileCell::new(0u32);
+    /// c.set_field_const::<30, 4>(0);
+    /// ```
+    ///
+    /// ```compile_fail,E0080
+    /// let c = vcell::VolatileCell::new(0u8);
+    /// c.set_field_const::<0, 9>(0);
+    /// ```
+    #[inline(always)]
+    pub fn set_field_const<const FIRST_BIT: u8, const BIT_COUNT: u8>(&self, value: T)
+        where T: Word
+    {
+        unsafe { ops::set_field_const::<T, FIRST_BIT, BIT_COUNT>(self.value.get(), value) }
     }
 
     /// Sets a collection of bits of the contained value with the bit-manipulation-engine, if
     /// enabled.
     /// See [NXP documentation] on the BME. This is an "OR" operation.
+    /// If the BME is not enabled, or cannot reach the address, this writes to the register's SET
+    /// alias if the `offset-alias` feature is enabled and the register has one, and otherwise
+    /// falls back to a volatile read-modify-write inside a critical section.
     ///
     /// [NXP documentation]: https://www.nxp.com/docs/en/application-note/AN4838.pdf
     #[inline(always)]
-    #[cfg(feature = "bit-manipulation")]
     pub fn set_bits(&self, bits_to_set: T)
-        where T: Copy
+        where T: Word
     {
-        unsafe {
-            let or_ptr = BmeOperation::Or.wrap_pointer(self.value.get());
-            ptr::write_volatile(or_ptr, bits_to_set)
-        }
+        unsafe { ops::set_bits(self.value.get(), bits_to_set) }
     }
 
     /// Clears a collection of bits of the contained value with the bit-manipulation-engine, if
     /// enabled.
     /// See [NXP documentation] on the BME. This is an "AND" operation.
     /// Note that the bits set in bits_to_clear get *cleared* in the register.
+    /// If the BME is not enabled, or cannot reach the address, this writes to the register's
+    /// CLEAR alias if the `offset-alias` feature is enabled and the register has one, and
+    /// otherwise falls back to a volatile read-modify-write inside a critical section.
     ///
     /// [NXP documentation]: https://www.nxp.com/docs/en/application-note/AN4838.pdf
     #[inline(always)]
-    #[cfg(feature = "bit-manipulation")]
     pub fn clear_bits(&self, bits_to_clear: T)
-        where T: Copy + core::ops::Not<Output = T>
+        where T: Word
     {
-        unsafe {
-            let and_ptr = BmeOperation::And.wrap_pointer(self.value.get());
-            ptr::write_volatile(and_ptr, !bits_to_clear)
-        }
+        unsafe { ops::clear_bits(self.value.get(), bits_to_clear) }
     }
 
     /// Inverts a collection of bits of the contained value with the bit-manipulation-engine, if
     /// enabled.
     /// See [NXP documentation] on the BME. This is an "XOR" operation.
+    /// If the BME is not enabled, or cannot reach the address, this writes to the register's
+    /// INVERT alias if the `offset-alias` feature is enabled and the register has one, and
+    /// otherwise falls back to a volatile read-modify-write inside a critical section.
     ///
     /// [NXP documentation]: https://www.nxp.com/docs/en/application-note/AN4838.pdf
     #[inline(always)]
-    #[cfg(feature = "bit-manipulation")]
     pub fn invert_bits(&self, bits_to_invert: T)
+        where T: Word
+    {
+        unsafe { ops::invert_bits(self.value.get(), bits_to_invert) }
+    }
+
+    /// Reads a single bit of the contained value and clears it, with the
+    /// bit-manipulation-engine, if enabled.
+    /// See [NXP documentation] on the BME. This is a "LAC1" decorated load.
+    /// Returns the value of the bit before it was cleared, as 0 or 1.
+    ///
+    /// [NXP documentation]: https://www.nxp.com/docs/en/application-note/AN4838.pdf
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn load_clear_bit(&self, bit: u8) -> T
+        where T: Copy
+    {
+        unsafe {
+            let lac_ptr = BmeOperation::LoadClearBit{bit}.wrap_pointer(self.value.get());
+            ptr::read_volatile(lac_ptr)
+        }
+    }
+
+    /// Like [`load_clear_bit`](#method.load_clear_bit), but returns an error instead of
+    /// panicking if the address or the bit is not supported by the bit-manipulation-engine.
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn try_load_clear_bit(&self, bit: u8) -> Result<T, Error>
+        where T: Copy
+    {
+        let lac_ptr = BmeOperation::LoadClearBit{bit}.try_wrap_pointer(self.value.get())?;
+        Ok(unsafe { ptr::read_volatile(lac_ptr) })
+    }
+
+    /// Reads a single bit of the contained value and sets it, with the bit-manipulation-engine,
+    /// if enabled.
+    /// See [NXP documentation] on the BME. This is a "LAS1" decorated load.
+    /// Returns the value of the bit before it was set, as 0 or 1.
+    ///
+    /// [NXP documentation]: https://www.nxp.com/docs/en/application-note/AN4838.pdf
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn load_set_bit(&self, bit: u8) -> T
+        where T: Copy
+    {
+        unsafe {
+            let las_ptr = BmeOperation::LoadSetBit{bit}.wrap_pointer(self.value.get());
+            ptr::read_volatile(las_ptr)
+        }
+    }
+
+    /// Like [`load_set_bit`](#method.load_set_bit), but returns an error instead of panicking
+    /// if the address or the bit is not supported by the bit-manipulation-engine.
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn try_load_set_bit(&self, bit: u8) -> Result<T, Error>
+        where T: Copy
+    {
+        let las_ptr = BmeOperation::LoadSetBit{bit}.try_wrap_pointer(self.value.get())?;
+        Ok(unsafe { ptr::read_volatile(las_ptr) })
+    }
+
+    /// Reads a sub-field of the contained value with the bit-manipulation-engine, if enabled.
+    /// See [NXP documentation] on the BME. This is a "UBFX" decorated load.
+    /// The field is returned shifted down to bit 0 and zero-extended.
+    ///
+    /// [NXP documentation]: https://www.nxp.com/docs/en/application-note/AN4838.pdf
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn get_field(&self, first_bit: u8, bit_count: u8) -> T
         where T: Copy
     {
         unsafe {
-            let xor_ptr = BmeOperation::Xor.wrap_pointer(self.value.get());
-            ptr::write_volatile(xor_ptr, bits_to_invert)
+            let op = BmeOperation::GetField{first_bit, bit_count};
+            ptr::read_volatile(op.wrap_pointer(self.value.get()))
         }
     }
 
+    /// Like [`get_field`](#method.get_field), but returns an error instead of panicking if the
+    /// address or the field is not supported by the bit-manipulation-engine.
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn try_get_field(&self, first_bit: u8, bit_count: u8) -> Result<T, Error>
+        where T: Copy
+    {
+        let op = BmeOperation::GetField{first_bit, bit_count};
+        let ubfx_ptr = op.try_wrap_pointer(self.value.get())?;
+        Ok(unsafe { ptr::read_volatile(ubfx_ptr) })
+    }
+
+    /// Returns the alias word of bit `bit_to_modify` of the value at `addr`, on the
+    /// [`Selected`](bitband/type.Selected.html) target.
     /// See [ARM documentation] and [ST documentation] on bit-banding.
     ///
     /// [ARM documentation]: http://infocenter.arm.com/help/index.jsp?topic=/com.arm.doc.ddi0337h/Behcjiic.html
     /// [ST documentation]: https://www.st.com/content/ccc/resource/technical/document/programming_manual/5b/ca/8d/83/56/7f/40/08/CD00228163.pdf/files/CD00228163.pdf/jcr:content/translations/en.CD00228163.pdf
     #[inline(always)]
     #[cfg(feature = "bit-banding")]
+    fn try_bitband_pointer(addr: *mut T, bit_to_modify: u8) -> Result<*mut u32, Error> {
+        bitband::try_alias_on::<bitband::Selected, T>(addr, bit_to_modify)
+    }
+
+    /// See [`try_bitband_pointer`](#method.try_bitband_pointer); panics instead of returning an
+    /// error.
+    #[inline(always)]
+    #[cfg(feature = "bit-banding")]
     fn bitband_pointer(addr: *mut T, bit_to_modify: u8) -> *mut u32 {
-        let addr = addr as usize as u32;
-        if addr < 0x20000000 || (addr > 0x200fffff && addr < 0x40000000) || addr > 0x400fffff {
-            panic!("Tried to use bit-banding on address 0x{:x?}, which is outside the bit-banded region");
-        }
-        if usize::from(bit_to_modify) > core::mem::size_of::<T>()*8 {
-            panic!("Tried to change bit {} of value whose size is {}", bit_to_modify, core::mem::size_of::<T>());
+        match Self::try_bitband_pointer(addr, bit_to_modify) {
+            Ok(ptr) => ptr,
+            Err(Error::AddressOutOfRegion) => {
+                panic!("Tried to use bit-banding on address 0x{:x?}, which is outside the bit-banded region", addr)
+            },
+            Err(_) => {
+                panic!("Tried to change bit {} of value whose size is {}", bit_to_modify, core::mem::size_of::<T>())
+            },
         }
-        // Shift left 5 bits, since each "normal" bit expands to a 32-bit word in the alias region
-        // Shift the bit_to_modify left 2 bits, since the output addresses must be 32-bit-aligned
-        // We can't overwrite bits because incoming addresses are aligned to T, and bit_to_modify
-        // is already range-checked against the size of T.  (That is, if the bit number is, say,
-        // 27, such that the top 2 bits might collide with lower bits in addr, then those 2 bits
-        // in addr must already be clear because of its alignment.)
-        let bb_offset = (addr & 0xfffff) << 5 | (u32::from(bit_to_modify & 0x1f)) << 2;
-        (((addr & 0xf0000000) | 0x02000000) | bb_offset) as usize as *mut u32
     }
 
-    /// Sets a single bit of the contained value with bit-banding, if enabled.
+    /// Sets a single bit of the contained value to `value` with bit-banding, if enabled.
     /// See [ARM documentation] and [ST documentation] on bit-banding.
+    /// If bit-banding is not enabled, or the address is outside of the bit-banded regions, this
+    /// falls back to a volatile read-modify-write inside a critical section.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit does not exist in `T`.
     ///
     /// [ARM documentation]: http://infocenter.arm.com/help/index.jsp?topic=/com.arm.doc.ddi0337h/Behcjiic.html
     /// [ST documentation]: https://www.st.com/content/ccc/resource/technical/document/programming_manual/5b/ca/8d/83/56/7f/40/08/CD00228163.pdf/files/CD00228163.pdf/jcr:content/translations/en.CD00228163.pdf
     #[inline(always)]
-    #[cfg(feature = "bit-banding")]
-    pub fn set_bit(&self, bit_to_set: u8, value: T)
-        where T: core::convert::Into<u32>
+    pub fn set_bit(&self, bit_to_set: u8, value: bool)
+        where T: Word
     {
-        let value = value.into();
-        if value > 1 {
-            panic!("value {:?} out of range", value)
-        }
-        unsafe {
-            ptr::write_volatile(Self::bitband_pointer(self.value.get(), bit_to_set), value)
-        }
+        unsafe { ops::set_bit(self.value.get(), bit_to_set, value) }
+    }
+
+    /// Like [`set_bit`](#method.set_bit), but returns an error instead of panicking if the bit
+    /// does not exist in `T`.
+    #[inline(always)]
+    pub fn try_set_bit(&self, bit_to_set: u8, value: bool) -> Result<(), Error>
+        where T: Word
+    {
+        unsafe { ops::try_set_bit(self.value.get(), bit_to_set, value) }
+    }
+
+    /// Clears a single bit of the contained value. This is `set_bit(bit_to_clear, false)`.
+    #[inline(always)]
+    pub fn clear_bit(&self, bit_to_clear: u8)
+        where T: Word
+    {
+        self.set_bit(bit_to_clear, false)
+    }
+
+    /// Like [`clear_bit`](#method.clear_bit), but returns an error instead of panicking if the
+    /// bit does not exist in `T`.
+    #[inline(always)]
+    pub fn try_clear_bit(&self, bit_to_clear: u8) -> Result<(), Error>
+        where T: Word
+    {
+        self.try_set_bit(bit_to_clear, false)
+    }
+
+    /// Inverts a single bit of the contained value.
+    ///
+    /// This is a single store to the bit-manipulation-engine's XOR or to the INVERT alias when
+    /// one of them reaches the address. Otherwise, with bit-banding, it reads the bit's alias
+    /// word and writes back its inverse: the other bits cannot be lost, but the toggle is not
+    /// atomic for the bit itself, so a write to the same bit from an interrupt handler in between
+    /// is lost. Without any of these it is a read-modify-write inside a critical section.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit does not exist in `T`.
+    #[inline(always)]
+    pub fn toggle_bit(&self, bit_to_toggle: u8)
+        where T: Word
+    {
+        unsafe { ops::toggle_bit(self.value.get(), bit_to_toggle) }
+    }
+
+    /// Like [`toggle_bit`](#method.toggle_bit), but returns an error instead of panicking if the
+    /// bit does not exist in `T`.
+    #[inline(always)]
+    pub fn try_toggle_bit(&self, bit_to_toggle: u8) -> Result<(), Error>
+        where T: Word
+    {
+        unsafe { ops::try_toggle_bit(self.value.get(), bit_to_toggle) }
+    }
+
+    /// Returns a single bit of the contained value.
+    ///
+    /// With bit-banding this reads the bit's alias word; otherwise it reads the whole value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit does not exist in `T`.
+    #[inline(always)]
+    pub fn get_bit(&self, bit_to_get: u8) -> bool
+        where T: Word
+    {
+        unsafe { ops::get_bit(self.value.get(), bit_to_get) }
+    }
+
+    /// Like [`get_bit`](#method.get_bit), but returns an error instead of panicking if the bit
+    /// does not exist in `T`.
+    #[inline(always)]
+    pub fn try_get_bit(&self, bit_to_get: u8) -> Result<bool, Error>
+        where T: Word
+    {
+        unsafe { ops::try_get_bit(self.value.get(), bit_to_get) }
     }
 }
 
@@ -249,14 +587,16 @@ mod test_bb {
     fn exhaustively_test_alignment() {
         for addr in 0x20000000..0x20100000 {
             for bit in 0..32 {
-                let out = VolatileCell::bitband_pointer(addr as usize as *mut u32, bit) as usize;
+                let out = bitband::try_alias_on::<bitband::CortexM, u32>(addr as usize as *mut u32, bit)
+                    .unwrap() as usize;
                 // All possible bitband outputs must be word-aligned
                 assert!((out & 0x3) == 0);
             }
         }
         for addr in 0x40000000..0x40100000 {
             for bit in 0..32 {
-                let out = VolatileCell::bitband_pointer(addr as usize as *mut u32, bit) as usize;
+                let out = bitband::try_alias_on::<bitband::CortexM, u32>(addr as usize as *mut u32, bit)
+                    .unwrap() as usize;
                 // All possible bitband outputs must be word-aligned
                 assert!((out & 0x3) == 0);
             }
@@ -289,4 +629,88 @@ mod test_bb {
     fn test_invalid_bit() {
         VolatileCell::bitband_pointer(0x20080000usize as *mut u8, 12);
     }
+
+    #[test]
+    fn test_try_bitband_pointer() {
+        assert_eq!(bitband::try_alias_on::<bitband::CortexM, u8>(0x20000000usize as *mut u8, 7),
+                   Ok(0x2200001cusize as *mut u32));
+        assert_eq!(bitband::try_alias_on::<bitband::CortexM, u8>(0x20000000usize as *mut u8, 8),
+                   Err(Error::BitOutOfRange));
+        assert_eq!(bitband::try_alias_on::<bitband::CortexM, u8>(0x30000000usize as *mut u8, 0),
+                   Err(Error::AddressOutOfRegion));
+    }
+}
+
+#[cfg(test)]
+mod test_cell {
+    use super::*;
+
+    #[test]
+    fn test_replace_and_take() {
+        let c = VolatileCell::new(5u32);
+        assert_eq!(c.replace(7), 5);
+        assert_eq!(c.take(), 7);
+        assert_eq!(c.get(), 0);
+    }
+
+    #[test]
+    fn test_swap() {
+        let a = VolatileCell::new(1u8);
+        let b = VolatileCell::new(2u8);
+        a.swap(&b);
+        assert_eq!((a.get(), b.get()), (2, 1));
+        a.swap(&a);
+        assert_eq!(a.get(), 2);
+    }
+
+    #[test]
+    fn test_pointer_constructors() {
+        let mut raw = 3u32;
+        {
+            let c = VolatileCell::from_mut(&mut raw);
+            c.set(4);
+            let alias = unsafe { VolatileCell::from_ptr(c.as_ptr()) };
+            assert_eq!(alias.get(), 4);
+        }
+        assert_eq!(raw, 4);
+
+        let mut c = VolatileCell::new(1u8);
+        *c.get_mut() = 2;
+        assert_eq!(c.into_inner(), 2);
+    }
+
+    #[test]
+    fn test_update_and_modify() {
+        let c = VolatileCell::new(0x10u16);
+        c.update(|v| v | 0x1);
+        assert_eq!(c.get(), 0x11);
+        c.modify(|v| v & !0x10);
+        assert_eq!(c.get(), 0x1);
+    }
+
+    #[test]
+    fn test_single_bits() {
+        let c = VolatileCell::new(0u16);
+        c.set_bit(15, true);
+        c.toggle_bit(3);
+        assert_eq!(c.get(), 0x8008);
+        assert!(c.get_bit(15));
+        c.clear_bit(15);
+        assert_eq!(c.try_get_bit(15), Ok(false));
+        assert_eq!(c.try_clear_bit(16), Err(Error::BitOutOfRange));
+        assert_eq!(c.get(), 0x0008);
+    }
+
+    #[test]
+    fn test_set_field_const() {
+        let c = VolatileCell::new(0xffff_0000u32);
+        c.set_field_const::<4, 8>(0x0000_0a50);
+        assert_eq!(c.get(), 0xffff_0a50);
+        // Wider than the BME supports, so always a read-modify-write
+        c.set_field_const::<0, 32>(0x1234_5678);
+        assert_eq!(c.get(), 0x1234_5678);
+        let b = VolatileCell::new(0u8);
+        b.set_field_const::<0, 8>(0xa5);
+        assert_eq!(b.get(), 0xa5);
+    }
 }
@@ -0,0 +1,92 @@
+//! A test-and-set lock built on the bit-manipulation-engine
+
+use core::sync::atomic::{compiler_fence, Ordering};
+
+use {BmeOperation, Error, VolatileCell};
+
+/// A lock flag that is taken with a single BME "LAS1" decorated load
+///
+/// Cortex-M0+ cores have no exclusive load / store instructions, so the usual way to share
+/// state between an interrupt handler and thread mode is to disable interrupts. The BME's
+/// load-and-set-1-bit operation reads and sets the flag in one bus cycle, which gives a lock that
+/// can be tried from any context without masking interrupts.
+///
+/// The lock never blocks: [`try_lock`] either takes it or reports that it is held. Spinning on
+/// it from an interrupt handler that preempted the holder would never terminate.
+///
+/// The flag must be at an address supported by the bit-manipulation-engine, normally the upper
+/// SRAM region. On Kinetis L parts the usual linker scripts start `.bss` in the lower SRAM
+/// region, which the BME does not reach, so [`try_new`] checks the address up front and
+/// [`try_lock`] cannot fail.
+///
+/// [`try_lock`]: #method.try_lock
+/// [`try_new`]: #method.try_new
+#[derive(Clone, Copy)]
+pub struct BmeLock {
+    flag: &'static VolatileCell<u32>,
+}
+
+impl BmeLock {
+    /// Creates a lock that uses `flag`, which is unlocked while it is zero
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit-manipulation-engine does not support the address of `flag`; see
+    /// [`try_new`](#method.try_new).
+    pub fn new(flag: &'static VolatileCell<u32>) -> Self {
+        match Self::try_new(flag) {
+            Ok(lock) => lock,
+            Err(e) => panic!("Tried to use BME lock flag at address {:p}: {}", flag.as_ptr(), e),
+        }
+    }
+
+    /// Like [`new`](#method.new), but returns an error instead of panicking if the
+    /// bit-manipulation-engine does not support the address of `flag`.
+    pub fn try_new(flag: &'static VolatileCell<u32>) -> Result<Self, Error> {
+        BmeOperation::LoadSetBit{bit: 0}.try_wrap_pointer(flag.as_ptr())?;
+        Ok(BmeLock { flag })
+    }
+
+    /// Tries to take the lock, and returns whether it was taken
+    #[inline(always)]
+    pub fn try_lock(&self) -> bool {
+        let was_locked = self.flag.load_set_bit(0) != 0;
+        compiler_fence(Ordering::Acquire);
+        !was_locked
+    }
+
+    /// Releases the lock
+    ///
+    /// This must only be called by the holder of the lock, that is after a call to
+    /// [`try_lock`](#method.try_lock) that returned `true`.
+    #[inline(always)]
+    pub fn unlock(&self) {
+        compiler_fence(Ordering::Release);
+        self.flag.set(0)
+    }
+
+    /// Returns whether the lock is currently held
+    #[inline(always)]
+    pub fn is_locked(&self) -> bool {
+        self.flag.get() != 0
+    }
+}
+
+// Every access to the flag is a single bus cycle, and the BME makes the test-and-set one of them.
+unsafe impl Send for BmeLock {}
+unsafe impl Sync for BmeLock {}
+
+#[cfg(test)]
+mod test_lock {
+    use core::ptr;
+
+    use super::*;
+
+    static mut FLAG: u32 = 0;
+
+    #[test]
+    fn test_unsupported_flag() {
+        let flag = unsafe { VolatileCell::from_ptr(ptr::addr_of_mut!(FLAG)) };
+        assert_eq!(BmeLock::try_new(flag).err(), Some(Error::AddressOutOfRegion));
+    }
+}
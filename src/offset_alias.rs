@@ -0,0 +1,186 @@
+//! Atomic SET / CLEAR / INVERT aliases at fixed offsets from a register
+//!
+//! Many microcontrollers without a BME or bit-banding let a register be updated without a
+//! read-modify-write through alias registers at fixed offsets from it: writing a mask to the
+//! SET alias ORs it into the register, to the CLEAR alias clears those bits, and to the INVERT
+//! alias XORs them in.
+//!
+//! The offsets and the address ranges that have aliases are described by an
+//! [`OffsetAliasTarget`]; the one used by [`VolatileCell`] and friends is [`Selected`], which is
+//! chosen with one of the `offset-alias-*` Cargo features and defaults to [`NoOffsetAlias`]. If
+//! several of them are enabled, the first of `offset-alias-rp2040`, `offset-alias-efm32s2` and
+//! `offset-alias-pic32` wins.
+//!
+//! [`OffsetAliasTarget`]: trait.OffsetAliasTarget.html
+//! [`VolatileCell`]: ../struct.VolatileCell.html
+//! [`Selected`]: type.Selected.html
+//! [`NoOffsetAlias`]: struct.NoOffsetAlias.html
+
+use core::mem;
+
+use {AddressRange, Error, PhysAddr32};
+
+/// A range of registers that have SET, CLEAR and INVERT aliases, and the aliases' offsets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OffsetAliasRegion {
+    /// The registers that have aliases
+    ///
+    /// Only registers whose address has none of the offset bits set have aliases: the aliases
+    /// themselves are in the same range.
+    pub source: AddressRange,
+    /// The offset of the SET alias
+    pub set: u32,
+    /// The offset of the CLEAR alias
+    pub clear: u32,
+    /// The offset of the INVERT alias
+    pub invert: u32,
+}
+
+impl OffsetAliasRegion {
+    /// Creates a new `OffsetAliasRegion`
+    pub const fn new(source: AddressRange, set: u32, clear: u32, invert: u32) -> Self {
+        OffsetAliasRegion { source, set, clear, invert }
+    }
+
+    #[inline(always)]
+    const fn offset(&self, operation: OffsetAliasOperation) -> u32 {
+        match operation {
+            OffsetAliasOperation::Set => self.set,
+            OffsetAliasOperation::Clear => self.clear,
+            OffsetAliasOperation::Invert => self.invert,
+        }
+    }
+}
+
+/// Description of the registers of a part that have offset aliases
+///
+/// The aliases are only used for 32-bit registers.
+pub trait OffsetAliasTarget {
+    /// The ranges of registers that have aliases
+    const REGIONS: &'static [OffsetAliasRegion];
+}
+
+/// Raspberry Pi RP2040: XOR at +0x1000, SET at +0x2000 and CLR at +0x3000 on the APB and AHB-Lite
+/// peripherals
+///
+/// The USB DPRAM and the SIO block have no aliases.
+pub struct Rp2040;
+
+impl OffsetAliasTarget for Rp2040 {
+    const REGIONS: &'static [OffsetAliasRegion] = &[
+        OffsetAliasRegion::new(AddressRange::new(0x4000_0000, 0x4008_0000), 0x2000, 0x3000, 0x1000),
+        OffsetAliasRegion::new(AddressRange::new(0x5000_0000, 0x5010_0000), 0x2000, 0x3000, 0x1000),
+        OffsetAliasRegion::new(AddressRange::new(0x5011_0000, 0x5012_0000), 0x2000, 0x3000, 0x1000),
+        OffsetAliasRegion::new(AddressRange::new(0x5020_0000, 0x5040_0000), 0x2000, 0x3000, 0x1000),
+    ];
+}
+
+/// Silicon Labs EFM32 / EFR32 Series 2: SET at +0x1000, CLR at +0x2000 and TGL at +0x3000 on
+/// the peripherals, through both their secure and non-secure addresses
+pub struct Efm32Series2;
+
+impl OffsetAliasTarget for Efm32Series2 {
+    const REGIONS: &'static [OffsetAliasRegion] = &[
+        OffsetAliasRegion::new(AddressRange::new(0x4000_0000, 0x6000_0000), 0x1000, 0x2000, 0x3000),
+    ];
+}
+
+/// Microchip PIC32MX: CLR at +0x4, SET at +0x8 and INV at +0xC on the special function
+/// registers, through their KSEG1 addresses
+pub struct Pic32;
+
+impl OffsetAliasTarget for Pic32 {
+    const REGIONS: &'static [OffsetAliasRegion] = &[
+        OffsetAliasRegion::new(AddressRange::new(0xbf80_0000, 0xbf90_0000), 0x8, 0x4, 0xc),
+    ];
+}
+
+/// No offset aliases
+///
+/// Every offset alias operation of [`VolatileCell`](../struct.VolatileCell.html) falls back to
+/// its software implementation.
+pub struct NoOffsetAlias;
+
+impl OffsetAliasTarget for NoOffsetAlias {
+    const REGIONS: &'static [OffsetAliasRegion] = &[];
+}
+
+select_target! {
+    /// The target used by the offset aliases of [`VolatileCell`](../struct.VolatileCell.html) and
+    /// friends
+    default NoOffsetAlias,
+    "offset-alias-rp2040" => Rp2040,
+    "offset-alias-efm32s2" => Efm32Series2,
+    "offset-alias-pic32" => Pic32
+}
+
+/// An update that an offset alias performs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OffsetAliasOperation {
+    /// ORs the written value into the register
+    Set,
+    /// Clears the bits set in the written value
+    Clear,
+    /// XORs the written value into the register
+    Invert,
+}
+
+/// Returns the alias of the register at `addr` for `operation` on the target `B`
+pub const fn alias_on<B: OffsetAliasTarget>(addr: PhysAddr32, operation: OffsetAliasOperation)
+    -> Result<PhysAddr32, Error>
+{
+    let mut i = 0;
+    while i < B::REGIONS.len() {
+        let region = &B::REGIONS[i];
+        let offsets = region.set | region.clear | region.invert;
+        if region.source.contains(addr) && addr.get() & offsets == 0 {
+            return Ok(PhysAddr32::new(addr.get() + region.offset(operation)));
+        }
+        i += 1;
+    }
+    Err(Error::AddressOutOfRegion)
+}
+
+/// Like [`alias_on`](fn.alias_on.html), on the [`Selected`](type.Selected.html) target
+pub const fn alias(addr: PhysAddr32, operation: OffsetAliasOperation) -> Result<PhysAddr32, Error> {
+    alias_on::<Selected>(addr, operation)
+}
+
+/// Like [`alias`](fn.alias.html), for a pointer to a `T`
+#[inline(always)]
+pub(crate) fn try_alias_pointer<T>(ptr: *mut T, operation: OffsetAliasOperation)
+    -> Result<*mut T, Error>
+{
+    if mem::size_of::<T>() != 4 {
+        return Err(Error::UnsupportedSize);
+    }
+    alias(PhysAddr32::try_from_ptr(ptr)?, operation).map(PhysAddr32::to_ptr)
+}
+
+#[cfg(test)]
+mod test_offset_alias {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        let rp = |addr, op| alias_on::<Rp2040>(PhysAddr32::new(addr), op).map(PhysAddr32::get);
+        assert_eq!(rp(0x4001_4004, OffsetAliasOperation::Set), Ok(0x4001_6004));
+        assert_eq!(rp(0x4001_4004, OffsetAliasOperation::Clear), Ok(0x4001_7004));
+        assert_eq!(rp(0x4001_4004, OffsetAliasOperation::Invert), Ok(0x4001_5004));
+        assert_eq!(rp(0x4001_6004, OffsetAliasOperation::Set), Err(Error::AddressOutOfRegion));
+        assert_eq!(rp(0x5010_0000, OffsetAliasOperation::Set), Err(Error::AddressOutOfRegion));
+        assert_eq!(rp(0xd000_0010, OffsetAliasOperation::Set), Err(Error::AddressOutOfRegion));
+
+        let efm = |addr, op| alias_on::<Efm32Series2>(PhysAddr32::new(addr), op).map(PhysAddr32::get);
+        assert_eq!(efm(0x5003_c000, OffsetAliasOperation::Clear), Ok(0x5003_e000));
+
+        let pic = |addr, op| alias_on::<Pic32>(PhysAddr32::new(addr), op).map(PhysAddr32::get);
+        assert_eq!(pic(0xbf88_6100, OffsetAliasOperation::Clear), Ok(0xbf88_6104));
+        assert_eq!(pic(0xbf88_6100, OffsetAliasOperation::Set), Ok(0xbf88_6108));
+        assert_eq!(pic(0xbf88_6100, OffsetAliasOperation::Invert), Ok(0xbf88_610c));
+        assert_eq!(pic(0xbf88_6104, OffsetAliasOperation::Set), Err(Error::AddressOutOfRegion));
+
+        assert_eq!(alias_on::<NoOffsetAlias>(PhysAddr32::new(0x4001_4004), OffsetAliasOperation::Set),
+                   Err(Error::AddressOutOfRegion));
+    }
+}
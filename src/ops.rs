@@ -0,0 +1,276 @@
+//! Bit manipulation through the best available mechanism
+//!
+//! Each operation is done by a hardware engine or alias when one is enabled and can reach the
+//! address, and otherwise by a volatile read-modify-write inside a critical section. These work on raw
+//! pointers so that `VolatileCell`, `VolPtr` and `Reg` can share them.
+
+use core::{mem, ptr};
+
+#[cfg(feature = "bit-manipulation")]
+use BmeOperation;
+#[cfg(feature = "bit-banding")]
+use VolatileCell;
+#[cfg(feature = "offset-alias")]
+use offset_alias::{self, OffsetAliasOperation};
+use {interrupt, Error, Word};
+
+/// Replaces the value at `p` with `f` of it, inside a critical section
+#[inline(always)]
+pub(crate) unsafe fn read_modify_write<T, F>(p: *mut T, f: F)
+    where T: Word,
+          F: FnOnce(T) -> T
+{
+    interrupt::free(|| ptr::write_volatile(p, f(ptr::read_volatile(p))))
+}
+
+#[inline(always)]
+pub(crate) unsafe fn set_bits<T: Word>(p: *mut T, bits_to_set: T) {
+    #[cfg(feature = "bit-manipulation")]
+    {
+        if let Ok(or_ptr) = BmeOperation::Or.try_wrap_pointer(p) {
+            return ptr::write_volatile(or_ptr, bits_to_set);
+        }
+    }
+    #[cfg(feature = "offset-alias")]
+    {
+        if let Ok(alias_ptr) = offset_alias::try_alias_pointer(p, OffsetAliasOperation::Set) {
+            return ptr::write_volatile(alias_ptr, bits_to_set);
+        }
+    }
+    read_modify_write(p, |v| v | bits_to_set)
+}
+
+#[inline(always)]
+pub(crate) unsafe fn clear_bits<T: Word>(p: *mut T, bits_to_clear: T) {
+    #[cfg(feature = "bit-manipulation")]
+    {
+        if let Ok(and_ptr) = BmeOperation::And.try_wrap_pointer(p) {
+            return ptr::write_volatile(and_ptr, !bits_to_clear);
+        }
+    }
+    #[cfg(feature = "offset-alias")]
+    {
+        if let Ok(alias_ptr) = offset_alias::try_alias_pointer(p, OffsetAliasOperation::Clear) {
+            return ptr::write_volatile(alias_ptr, bits_to_clear);
+        }
+    }
+    read_modify_write(p, |v| v & !bits_to_clear)
+}
+
+/// Inverts `bits_to_invert` with a single store to the BME or the INVERT alias, and returns
+/// whether one of them could reach `p`
+#[inline(always)]
+#[cfg_attr(not(any(feature = "bit-manipulation", feature = "offset-alias")), allow(unused_variables))]
+unsafe fn try_invert_bits_in_hardware<T: Word>(p: *mut T, bits_to_invert: T) -> bool {
+    #[cfg(feature = "bit-manipulation")]
+    {
+        if let Ok(xor_ptr) = BmeOperation::Xor.try_wrap_pointer(p) {
+            ptr::write_volatile(xor_ptr, bits_to_invert);
+            return true;
+        }
+    }
+    #[cfg(feature = "offset-alias")]
+    {
+        if let Ok(alias_ptr) = offset_alias::try_alias_pointer(p, OffsetAliasOperation::Invert) {
+            ptr::write_volatile(alias_ptr, bits_to_invert);
+            return true;
+        }
+    }
+    false
+}
+
+#[inline(always)]
+pub(crate) unsafe fn invert_bits<T: Word>(p: *mut T, bits_to_invert: T) {
+    if !try_invert_bits_in_hardware(p, bits_to_invert) {
+        read_modify_write(p, |v| v ^ bits_to_invert)
+    }
+}
+
+#[inline(always)]
+pub(crate) unsafe fn try_set_field<T: Word>(p: *mut T, first_bit: u8, bit_count: u8, value: T)
+    -> Result<(), Error>
+{
+    #[cfg(feature = "bit-manipulation")]
+    {
+        let op = BmeOperation::SetField{first_bit, bit_count};
+        if let Ok(bfi_ptr) = op.try_wrap_pointer(p) {
+            ptr::write_volatile(bfi_ptr, value);
+            return Ok(());
+        }
+    }
+    let mask = field_mask::<T>(first_bit, bit_count)?;
+    read_modify_write(p, |v| (v & !mask) | (value & mask));
+    Ok(())
+}
+
+/// Returns the mask of a field, if it fits in `T`
+#[inline(always)]
+pub(crate) fn field_mask<T: Word>(first_bit: u8, bit_count: u8) -> Result<T, Error> {
+    if bit_count == 0 || u32::from(first_bit) + u32::from(bit_count) > T::BITS {
+        return Err(Error::BadFieldWidth);
+    }
+    Ok(T::field_mask(first_bit, bit_count))
+}
+
+#[inline(always)]
+pub(crate) unsafe fn set_field<T: Word>(p: *mut T, first_bit: u8, bit_count: u8, value: T) {
+    if let Err(e) = try_set_field(p, first_bit, bit_count, value) {
+        panic!("Tried to set {} bits starting at bit {} of a {}-bit value: {}",
+               bit_count, first_bit, T::BITS, e);
+    }
+}
+
+/// Like `set_field`, for a field given as const generic parameters
+///
+/// A field that does not fit in `T` fails the build. The BME is used if it supports the field.
+#[inline(always)]
+pub(crate) unsafe fn set_field_const<T, const FIRST_BIT: u8, const BIT_COUNT: u8>(p: *mut T, value: T)
+    where T: Word
+{
+    const {
+        assert!(BIT_COUNT >= 1, "BIT_COUNT must be at least 1");
+        assert!(FIRST_BIT as usize + BIT_COUNT as usize <= mem::size_of::<T>() * 8,
+                "field does not fit in the register type");
+    }
+    #[cfg(feature = "bit-manipulation")]
+    {
+        use BmeField;
+
+        if let Ok(bits) = BmeField::<T, FIRST_BIT, BIT_COUNT>::BITS {
+            if let Ok(bfi_ptr) = BmeField::<T, FIRST_BIT, BIT_COUNT>::OP.try_decorate(p, bits) {
+                return ptr::write_volatile(bfi_ptr, value);
+            }
+        }
+    }
+    let mask = T::field_mask(FIRST_BIT, BIT_COUNT);
+    read_modify_write(p, |v| (v & !mask) | (value & mask))
+}
+
+/// Returns the bit-band alias word of bit `bit` of `p`, if bit-banding can reach it
+#[cfg(feature = "bit-banding")]
+#[inline(always)]
+fn bitband_alias<T>(p: *mut T, bit: u8) -> Option<*mut u32> {
+    VolatileCell::try_bitband_pointer(p, bit).ok()
+}
+
+/// Returns the bit-band alias word of bit `bit` of `p`, if bit-banding can reach it
+#[cfg(not(feature = "bit-banding"))]
+#[inline(always)]
+fn bitband_alias<T>(_p: *mut T, _bit: u8) -> Option<*mut u32> {
+    None
+}
+
+#[inline(always)]
+fn check_bit<T: Word>(bit: u8) -> Result<(), Error> {
+    if u32::from(bit) >= T::BITS {
+        return Err(Error::BitOutOfRange);
+    }
+    Ok(())
+}
+
+#[inline(always)]
+fn unwrap_bit<T: Word, R>(result: Result<R, Error>, bit: u8) -> R {
+    match result {
+        Ok(r) => r,
+        Err(_) => panic!("Tried to access bit {} of a {}-bit value", bit, T::BITS),
+    }
+}
+
+#[inline(always)]
+pub(crate) unsafe fn try_set_bit<T: Word>(p: *mut T, bit_to_set: u8, value: bool)
+    -> Result<(), Error>
+{
+    check_bit::<T>(bit_to_set)?;
+    if let Some(bb_ptr) = bitband_alias(p, bit_to_set) {
+        ptr::write_volatile(bb_ptr, u32::from(value));
+        return Ok(());
+    }
+    let bit = T::bit(bit_to_set);
+    read_modify_write(p, |v| if value { v | bit } else { v & !bit });
+    Ok(())
+}
+
+#[inline(always)]
+pub(crate) unsafe fn set_bit<T: Word>(p: *mut T, bit_to_set: u8, value: bool) {
+    unwrap_bit::<T, _>(try_set_bit(p, bit_to_set, value), bit_to_set)
+}
+
+#[inline(always)]
+pub(crate) unsafe fn try_get_bit<T: Word>(p: *mut T, bit_to_get: u8) -> Result<bool, Error> {
+    check_bit::<T>(bit_to_get)?;
+    if let Some(bb_ptr) = bitband_alias(p, bit_to_get) {
+        return Ok(ptr::read_volatile(bb_ptr) != 0);
+    }
+    let bit = T::bit(bit_to_get);
+    Ok(ptr::read_volatile(p) & bit == bit)
+}
+
+#[inline(always)]
+pub(crate) unsafe fn get_bit<T: Word>(p: *mut T, bit_to_get: u8) -> bool {
+    unwrap_bit::<T, _>(try_get_bit(p, bit_to_get), bit_to_get)
+}
+
+/// Inverts one bit, with a single store to the BME or the INVERT alias if possible. The
+/// bit-band alias is read and written back, which leaves the other bits alone but is not atomic
+/// for the bit itself.
+#[inline(always)]
+pub(crate) unsafe fn try_toggle_bit<T: Word>(p: *mut T, bit_to_toggle: u8) -> Result<(), Error> {
+    check_bit::<T>(bit_to_toggle)?;
+    let bit = T::bit(bit_to_toggle);
+    if try_invert_bits_in_hardware(p, bit) {
+        return Ok(());
+    }
+    if let Some(bb_ptr) = bitband_alias(p, bit_to_toggle) {
+        ptr::write_volatile(bb_ptr, ptr::read_volatile(bb_ptr) ^ 1);
+        return Ok(());
+    }
+    read_modify_write(p, |v| v ^ bit);
+    Ok(())
+}
+
+#[inline(always)]
+pub(crate) unsafe fn toggle_bit<T: Word>(p: *mut T, bit_to_toggle: u8) {
+    unwrap_bit::<T, _>(try_toggle_bit(p, bit_to_toggle), bit_to_toggle)
+}
+
+#[cfg(test)]
+mod test_ops {
+    use super::*;
+
+    #[test]
+    fn test_fallback() {
+        let mut v = 0x0fu8;
+        unsafe {
+            set_bits(&mut v, 0x30);
+            assert_eq!(v, 0x3f);
+            clear_bits(&mut v, 0x03);
+            assert_eq!(v, 0x3c);
+            invert_bits(&mut v, 0x81);
+            assert_eq!(v, 0xbd);
+            set_field(&mut v, 4, 3, 0x25);
+            assert_eq!(v, 0xad);
+            set_bit(&mut v, 1, true);
+            set_bit(&mut v, 7, false);
+            assert_eq!(v, 0x2f);
+            toggle_bit(&mut v, 0);
+            toggle_bit(&mut v, 4);
+            assert_eq!(v, 0x3e);
+            assert!(get_bit(&mut v, 5));
+            assert!(!get_bit(&mut v, 6));
+        }
+    }
+
+    #[test]
+    fn test_fallback_errors() {
+        let mut v = 0u16;
+        unsafe {
+            assert_eq!(try_set_field(&mut v, 12, 5, 0), Err(Error::BadFieldWidth));
+            assert_eq!(try_set_field(&mut v, 0, 0, 0), Err(Error::BadFieldWidth));
+            assert_eq!(try_set_field(&mut v, 0, 16, 0xffff), Ok(()));
+            assert_eq!(try_set_bit(&mut v, 16, true), Err(Error::BitOutOfRange));
+            assert_eq!(try_get_bit(&mut v, 16), Err(Error::BitOutOfRange));
+            assert_eq!(try_toggle_bit(&mut v, 16), Err(Error::BitOutOfRange));
+        }
+        assert_eq!(v, 0xffff);
+    }
+}
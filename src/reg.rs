@@ -0,0 +1,367 @@
+//! Zero-sized registers whose address is known at compile time
+
+use core::marker::PhantomData;
+use core::mem;
+
+#[cfg(feature = "bit-manipulation")]
+use {BmeOperation, PhysAddr32};
+use {Error, VolPtr, Word};
+
+/// A register of type `T` at the fixed address `ADDR`
+///
+/// `Reg` is zero-sized: the address is part of the type, so a `Reg` costs no storage and every
+/// access compiles down to a load or store of a constant address. Because the address is known
+/// at compile time, an `ADDR` that is misaligned for `T` is rejected with a build error, as is one
+/// outside of the range supported by the bit-manipulation-engine as soon as one of the decorated
+/// loads (which have no fallback) is used. The other bit operations pick the hardware engine or
+/// the software fallback for the constant address when the code is compiled.
+///
+/// Accesses go through [`VolPtr`], so no reference to the register is ever created.
+///
+/// ```no_run
+/// let odr = unsafe { vcell::Reg::<0x4001_080c, u32>::new() };
+/// odr.set(1);
+/// ```
+///
+/// A misaligned address fails the build:
+///
+/// ```compile_fail,E0080
+/// let odr = unsafe { vcell::Reg::<0x4001_080d, u32>::new() };
+/// odr.set(1);
+/// ```
+///
+/// [`VolPtr`]: struct.VolPtr.html
+pub struct Reg<const ADDR: usize, T> {
+    _marker: PhantomData<*mut T>,
+}
+
+impl<const ADDR: usize, T> Reg<ADDR, T> {
+    const ALIGNED: () = assert!(
+        ADDR & (mem::align_of::<T>() - 1) == 0,
+        "register address is misaligned for the register type"
+    );
+    #[cfg(feature = "bit-manipulation")]
+    const ADDR32: PhysAddr32 = match PhysAddr32::try_from_usize(ADDR) {
+        Ok(addr) => addr,
+        Err(_) => panic!("register address does not fit in 32 bits"),
+    };
+    #[cfg(feature = "bit-manipulation")]
+    const BME_SUPPORTED: () = {
+        assert!(
+            BmeOperation::Or.supports(Self::ADDR32),
+            "register address is not supported by the bit-manipulation-engine"
+        );
+        assert!(
+            BmeOperation::Or.check_size(mem::size_of::<T>()).is_ok(),
+            "the bit-manipulation-engine only supports 8-, 16- and 32-bit registers"
+        );
+    };
+    #[cfg(feature = "bit-manipulation")]
+    const BME_FIELD_SUPPORTED: () = {
+        let () = Self::BME_SUPPORTED;
+        assert!(
+            BmeOperation::SetField{first_bit: 0, bit_count: 1}.supports(Self::ADDR32),
+            "register address is not supported by the bit-manipulation-engine's field operations"
+        );
+    };
+
+    /// Creates a handle to the register
+    ///
+    /// # Safety
+    ///
+    /// `ADDR` must stay valid for volatile reads and writes of `T` for as long as the handle is
+    /// used.
+    #[inline(always)]
+    pub const unsafe fn new() -> Self {
+        let () = Self::ALIGNED;
+        Reg { _marker: PhantomData }
+    }
+
+    /// Returns the address of the register
+    #[inline(always)]
+    pub const fn addr(&self) -> usize {
+        ADDR
+    }
+
+    /// Returns a pointer to the register
+    #[inline(always)]
+    pub fn as_ptr(&self) -> *mut T {
+        ADDR as *mut T
+    }
+
+    #[inline(always)]
+    fn volptr(&self) -> VolPtr<T> {
+        unsafe { VolPtr::new(self.as_ptr()) }
+    }
+
+    /// Returns a copy of the register value
+    #[inline(always)]
+    pub fn get(&self) -> T
+        where T: Copy
+    {
+        self.volptr().get()
+    }
+
+    /// Sets the register value
+    #[inline(always)]
+    pub fn set(&self, value: T)
+        where T: Copy
+    {
+        self.volptr().set(value)
+    }
+
+    /// Performs a read-modify-write of the register value.
+    /// See [`VolatileCell::modify`].
+    ///
+    /// [`VolatileCell::modify`]: struct.VolatileCell.html#method.modify
+    #[inline(always)]
+    pub fn modify<F>(&self, f: F)
+        where T: Copy,
+              F: FnOnce(T) -> T
+    {
+        self.set(f(self.get()))
+    }
+
+    /// Sets a sub-field of the register.
+    /// See [`VolatileCell::set_field`].
+    ///
+    /// [`VolatileCell::set_field`]: struct.VolatileCell.html#method.set_field
+    #[inline(always)]
+    pub fn set_field(&self, first_bit: u8, bit_count: u8, value: T)
+        where T: Word
+    {
+        self.volptr().set_field(first_bit, bit_count, value)
+    }
+
+    /// Like [`set_field`](#method.set_field), but returns an error instead of panicking if the
+    /// field is not supported.
+    #[inline(always)]
+    pub fn try_set_field(&self, first_bit: u8, bit_count: u8, value: T) -> Result<(), Error>
+        where T: Word
+    {
+        self.volptr().try_set_field(first_bit, bit_count, value)
+    }
+
+    /// Sets a sub-field, given as const generic parameters.
+    /// See [`VolatileCell::set_field_const`].
+    ///
+    /// The field is checked at compile time, and when the register address is supported by the
+    /// bit-manipulation-engine the decorated address is a constant.
+    ///
+    /// [`VolatileCell::set_field_const`]: struct.VolatileCell.html#method.set_field_const
+    #[inline(always)]
+    pub fn set_field_const<const FIRST_BIT: u8, const BIT_COUNT: u8>(&self, value: T)
+        where T: Word
+    {
+        self.volptr().set_field_const::<FIRST_BIT, BIT_COUNT>(value)
+    }
+
+    /// Sets a collection of bits of the register.
+    /// See [`VolatileCell::set_bits`].
+    ///
+    /// [`VolatileCell::set_bits`]: struct.VolatileCell.html#method.set_bits
+    #[inline(always)]
+    pub fn set_bits(&self, bits_to_set: T)
+        where T: Word
+    {
+        self.volptr().set_bits(bits_to_set)
+    }
+
+    /// Clears a collection of bits of the register.
+    /// See [`VolatileCell::clear_bits`].
+    ///
+    /// [`VolatileCell::clear_bits`]: struct.VolatileCell.html#method.clear_bits
+    #[inline(always)]
+    pub fn clear_bits(&self, bits_to_clear: T)
+        where T: Word
+    {
+        self.volptr().clear_bits(bits_to_clear)
+    }
+
+    /// Inverts a collection of bits of the register.
+    /// See [`VolatileCell::invert_bits`].
+    ///
+    /// [`VolatileCell::invert_bits`]: struct.VolatileCell.html#method.invert_bits
+    #[inline(always)]
+    pub fn invert_bits(&self, bits_to_invert: T)
+        where T: Word
+    {
+        self.volptr().invert_bits(bits_to_invert)
+    }
+
+    /// Reads a single bit of the register and clears it with the bit-manipulation-engine.
+    /// See [`VolatileCell::load_clear_bit`].
+    ///
+    /// [`VolatileCell::load_clear_bit`]: struct.VolatileCell.html#method.load_clear_bit
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn load_clear_bit(&self, bit: u8) -> T
+        where T: Copy
+    {
+        let () = Self::BME_SUPPORTED;
+        self.volptr().load_clear_bit(bit)
+    }
+
+    /// Like [`load_clear_bit`](#method.load_clear_bit), but returns an error instead of panicking if the
+    /// bit is out of range.
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn try_load_clear_bit(&self, bit: u8) -> Result<T, Error>
+        where T: Copy
+    {
+        let () = Self::BME_SUPPORTED;
+        self.volptr().try_load_clear_bit(bit)
+    }
+
+    /// Reads a single bit of the register and sets it with the bit-manipulation-engine.
+    /// See [`VolatileCell::load_set_bit`].
+    ///
+    /// ```no_run
+    /// let flags = unsafe { vcell::Reg::<0x4004_8000, u32>::new() };
+    /// let _ = flags.load_set_bit(3);
+    /// ```
+    ///
+    /// An address the BME does not reach fails the build:
+    ///
+    /// ```compile_fail,E0080
+    /// let flags = unsafe { vcell::Reg::<0x6000_0000, u32>::new() };
+    /// let _ = flags.load_set_bit(3);
+    /// ```
+    ///
+    /// [`VolatileCell::load_set_bit`]: struct.VolatileCell.html#method.load_set_bit
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn load_set_bit(&self, bit: u8) -> T
+        where T: Copy
+    {
+        let () = Self::BME_SUPPORTED;
+        self.volptr().load_set_bit(bit)
+    }
+
+    /// Like [`load_set_bit`](#method.load_set_bit), but returns an error instead of panicking if the
+    /// bit is out of range.
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn try_load_set_bit(&self, bit: u8) -> Result<T, Error>
+        where T: Copy
+    {
+        let () = Self::BME_SUPPORTED;
+        self.volptr().try_load_set_bit(bit)
+    }
+
+    /// Reads a sub-field of the register with the bit-manipulation-engine.
+    /// See [`VolatileCell::get_field`].
+    ///
+    /// [`VolatileCell::get_field`]: struct.VolatileCell.html#method.get_field
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn get_field(&self, first_bit: u8, bit_count: u8) -> T
+        where T: Copy
+    {
+        let () = Self::BME_FIELD_SUPPORTED;
+        self.volptr().get_field(first_bit, bit_count)
+    }
+
+    /// Like [`get_field`](#method.get_field), but returns an error instead of panicking if the
+    /// bit is out of range.
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn try_get_field(&self, first_bit: u8, bit_count: u8) -> Result<T, Error>
+        where T: Copy
+    {
+        let () = Self::BME_FIELD_SUPPORTED;
+        self.volptr().try_get_field(first_bit, bit_count)
+    }
+
+    /// Sets a single bit of the register to `value`.
+    /// See [`VolatileCell::set_bit`].
+    ///
+    /// [`VolatileCell::set_bit`]: struct.VolatileCell.html#method.set_bit
+    #[inline(always)]
+    pub fn set_bit(&self, bit_to_set: u8, value: bool)
+        where T: Word
+    {
+        self.volptr().set_bit(bit_to_set, value)
+    }
+
+    /// Like [`set_bit`](#method.set_bit), but returns an error instead of panicking
+    /// if the bit does not exist in `T`.
+    #[inline(always)]
+    pub fn try_set_bit(&self, bit_to_set: u8, value: bool) -> Result<(), Error>
+        where T: Word
+    {
+        self.volptr().try_set_bit(bit_to_set, value)
+    }
+
+    /// Clears a single bit of the register.
+    /// See [`VolatileCell::clear_bit`].
+    ///
+    /// [`VolatileCell::clear_bit`]: struct.VolatileCell.html#method.clear_bit
+    #[inline(always)]
+    pub fn clear_bit(&self, bit_to_clear: u8)
+        where T: Word
+    {
+        self.volptr().clear_bit(bit_to_clear)
+    }
+
+    /// Like [`clear_bit`](#method.clear_bit), but returns an error instead of panicking
+    /// if the bit does not exist in `T`.
+    #[inline(always)]
+    pub fn try_clear_bit(&self, bit_to_clear: u8) -> Result<(), Error>
+        where T: Word
+    {
+        self.volptr().try_clear_bit(bit_to_clear)
+    }
+
+    /// Inverts a single bit of the register.
+    /// See [`VolatileCell::toggle_bit`].
+    ///
+    /// [`VolatileCell::toggle_bit`]: struct.VolatileCell.html#method.toggle_bit
+    #[inline(always)]
+    pub fn toggle_bit(&self, bit_to_toggle: u8)
+        where T: Word
+    {
+        self.volptr().toggle_bit(bit_to_toggle)
+    }
+
+    /// Like [`toggle_bit`](#method.toggle_bit), but returns an error instead of panicking
+    /// if the bit does not exist in `T`.
+    #[inline(always)]
+    pub fn try_toggle_bit(&self, bit_to_toggle: u8) -> Result<(), Error>
+        where T: Word
+    {
+        self.volptr().try_toggle_bit(bit_to_toggle)
+    }
+
+    /// Returns a single bit of the register.
+    /// See [`VolatileCell::get_bit`].
+    ///
+    /// [`VolatileCell::get_bit`]: struct.VolatileCell.html#method.get_bit
+    #[inline(always)]
+    pub fn get_bit(&self, bit_to_get: u8) -> bool
+        where T: Word
+    {
+        self.volptr().get_bit(bit_to_get)
+    }
+
+    /// Like [`get_bit`](#method.get_bit), but returns an error instead of panicking
+    /// if the bit does not exist in `T`.
+    #[inline(always)]
+    pub fn try_get_bit(&self, bit_to_get: u8) -> Result<bool, Error>
+        where T: Word
+    {
+        self.volptr().try_get_bit(bit_to_get)
+    }
+}
+
+#[cfg(test)]
+mod test_reg {
+    use super::*;
+
+    #[test]
+    fn test_zero_sized() {
+        let reg = unsafe { Reg::<0x4000_0000, u32>::new() };
+        assert_eq!(mem::size_of_val(&reg), 0);
+        assert_eq!(reg.addr(), 0x4000_0000);
+    }
+}
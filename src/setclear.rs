@@ -0,0 +1,195 @@
+//! Registers that are updated through dedicated set and clear registers
+
+use {Error, VolatileCell, Word};
+
+/// A pair of registers that set and clear the bits written to them, like the nRF `OUTSET` and
+/// `OUTCLR` registers
+///
+/// Writing a mask to the set register sets those bits of the underlying register and writing
+/// it to the clear register clears them; the other bits are unaffected, so no read-modify-write
+/// is needed.
+pub struct SetClearPair<'a, T: 'a> {
+    set: &'a VolatileCell<T>,
+    clear: &'a VolatileCell<T>,
+}
+
+impl<'a, T> SetClearPair<'a, T> {
+    /// Creates a new `SetClearPair` from its set and clear registers
+    pub fn new(set: &'a VolatileCell<T>, clear: &'a VolatileCell<T>) -> Self {
+        SetClearPair { set, clear }
+    }
+
+    /// Sets the bits that are set in `bits_to_set`
+    #[inline(always)]
+    pub fn set_bits(&self, bits_to_set: T)
+        where T: Word
+    {
+        self.set.set(bits_to_set)
+    }
+
+    /// Clears the bits that are set in `bits_to_clear`
+    #[inline(always)]
+    pub fn clear_bits(&self, bits_to_clear: T)
+        where T: Word
+    {
+        self.clear.set(bits_to_clear)
+    }
+
+    /// Sets the bits selected by `mask` to the corresponding bits of `value`
+    ///
+    /// This takes two writes, one to each register.
+    #[inline(always)]
+    pub fn write_masked(&self, mask: T, value: T)
+        where T: Word
+    {
+        self.set_bits(mask & value);
+        self.clear_bits(mask & !value)
+    }
+}
+
+impl<'a, T> Clone for SetClearPair<'a, T> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for SetClearPair<'a, T> {}
+
+/// A single register whose lower half sets and upper half clears bits, like the STM32 `BSRR`
+///
+/// Masks refer to the bits of the lower half, that is to bits 0-15 of a 32-bit `BSRR`. Writing
+/// a bit of the lower half sets the corresponding bit of the underlying register, and writing a
+/// bit of the upper half clears it; if both are written, setting wins.
+pub struct SetResetRegister<'a, T: 'a> {
+    register: &'a VolatileCell<T>,
+}
+
+impl<'a, T> SetResetRegister<'a, T> {
+    /// Creates a new `SetResetRegister` from the register
+    pub fn new(register: &'a VolatileCell<T>) -> Self {
+        SetResetRegister { register }
+    }
+
+    #[inline(always)]
+    fn check(mask: T) -> Result<(), Error>
+        where T: Word
+    {
+        let upper = T::field_mask((T::BITS / 2) as u8, (T::BITS / 2) as u8);
+        if mask & upper != T::ZERO {
+            return Err(Error::BadValue);
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn unwrap(result: Result<(), Error>)
+        where T: Word
+    {
+        if result.is_err() {
+            panic!("Tried to set or clear bits outside of the lower {} bits", T::BITS / 2);
+        }
+    }
+
+    /// Sets the bits that are set in `bits_to_set`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits_to_set` has bits set in the upper half.
+    #[inline(always)]
+    pub fn set_bits(&self, bits_to_set: T)
+        where T: Word
+    {
+        Self::unwrap(self.try_set_bits(bits_to_set))
+    }
+
+    /// Like [`set_bits`](#method.set_bits), but returns an error instead of panicking.
+    #[inline(always)]
+    pub fn try_set_bits(&self, bits_to_set: T) -> Result<(), Error>
+        where T: Word
+    {
+        Self::check(bits_to_set)?;
+        self.register.set(bits_to_set);
+        Ok(())
+    }
+
+    /// Clears the bits that are set in `bits_to_clear`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits_to_clear` has bits set in the upper half.
+    #[inline(always)]
+    pub fn clear_bits(&self, bits_to_clear: T)
+        where T: Word
+    {
+        Self::unwrap(self.try_clear_bits(bits_to_clear))
+    }
+
+    /// Like [`clear_bits`](#method.clear_bits), but returns an error instead of panicking.
+    #[inline(always)]
+    pub fn try_clear_bits(&self, bits_to_clear: T) -> Result<(), Error>
+        where T: Word
+    {
+        Self::check(bits_to_clear)?;
+        self.register.set(bits_to_clear.shift_left(T::BITS / 2));
+        Ok(())
+    }
+
+    /// Sets the bits selected by `mask` to the corresponding bits of `value`, with a single write
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mask` has bits set in the upper half.
+    #[inline(always)]
+    pub fn write_masked(&self, mask: T, value: T)
+        where T: Word
+    {
+        Self::unwrap(self.try_write_masked(mask, value))
+    }
+
+    /// Like [`write_masked`](#method.write_masked), but returns an error instead of panicking.
+    #[inline(always)]
+    pub fn try_write_masked(&self, mask: T, value: T) -> Result<(), Error>
+        where T: Word
+    {
+        Self::check(mask)?;
+        self.register.set((mask & value) | (mask & !value).shift_left(T::BITS / 2));
+        Ok(())
+    }
+}
+
+impl<'a, T> Clone for SetResetRegister<'a, T> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for SetResetRegister<'a, T> {}
+
+#[cfg(test)]
+mod test_setclear {
+    use super::*;
+
+    #[test]
+    fn test_pair() {
+        let (set, clear) = (VolatileCell::new(0u32), VolatileCell::new(0u32));
+        let pair = SetClearPair::new(&set, &clear);
+        pair.write_masked(0x0000_00f0, 0x0000_0030);
+        assert_eq!((set.get(), clear.get()), (0x0000_0030, 0x0000_00c0));
+        pair.clear_bits(0x1);
+        assert_eq!(clear.get(), 0x1);
+    }
+
+    #[test]
+    fn test_bsrr() {
+        let bsrr = VolatileCell::new(0u32);
+        let gpio = SetResetRegister::new(&bsrr);
+        gpio.write_masked(0x00f0, 0x0030);
+        assert_eq!(bsrr.get(), 0x00c0_0030);
+        gpio.clear_bits(0x8000);
+        assert_eq!(bsrr.get(), 0x8000_0000);
+        assert_eq!(gpio.try_set_bits(0x1_0000), Err(Error::BadValue));
+        assert_eq!(bsrr.get(), 0x8000_0000);
+    }
+}
@@ -0,0 +1,311 @@
+//! Volatile access through a raw address, without creating references
+
+use core::fmt;
+use core::ptr;
+
+#[cfg(feature = "bit-manipulation")]
+use BmeOperation;
+use {ops, Error, Word};
+
+/// A raw address with [volatile] read / write operations
+///
+/// `VolPtr` offers the same operations as [`VolatileCell`], but it holds the address of the
+/// value instead of being placed at it. No Rust reference to the pointed-to memory is ever
+/// created, so the compiler is never told that the memory is dereferenceable and cannot
+/// introduce spurious reads of it. This makes `VolPtr` the more conservative choice for
+/// memory-mapped registers where reads have side effects.
+///
+/// [volatile]: https://doc.rust-lang.org/std/ptr/fn.read_volatile.html
+/// [`VolatileCell`]: struct.VolatileCell.html
+pub struct VolPtr<T> {
+    ptr: *mut T,
+}
+
+impl<T> VolPtr<T> {
+    /// Creates a new `VolPtr` for the given pointer
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be non-null and aligned for `T`, and it must stay valid for volatile reads and
+    /// writes of `T` for as long as this `VolPtr` or any copy of it is used.
+    #[inline(always)]
+    pub const unsafe fn new(ptr: *mut T) -> Self {
+        VolPtr { ptr }
+    }
+
+    /// Creates a new `VolPtr` for the given physical address
+    ///
+    /// # Safety
+    ///
+    /// See [`new`].
+    ///
+    /// [`new`]: #method.new
+    #[inline(always)]
+    pub unsafe fn from_addr(addr: usize) -> Self {
+        VolPtr { ptr: addr as *mut T }
+    }
+
+    /// Returns the wrapped pointer
+    #[inline(always)]
+    pub fn as_ptr(self) -> *mut T {
+        self.ptr
+    }
+
+    /// Returns a copy of the pointed-to value
+    #[inline(always)]
+    pub fn get(self) -> T
+        where T: Copy
+    {
+        unsafe { ptr::read_volatile(self.ptr) }
+    }
+
+    /// Sets the pointed-to value
+    #[inline(always)]
+    pub fn set(self, value: T)
+        where T: Copy
+    {
+        unsafe { ptr::write_volatile(self.ptr, value) }
+    }
+
+    /// Sets a sub-field of the pointed-to value.
+    /// See [`VolatileCell::set_field`].
+    ///
+    /// [`VolatileCell::set_field`]: struct.VolatileCell.html#method.set_field
+    #[inline(always)]
+    pub fn set_field(self, first_bit: u8, bit_count: u8, value: T)
+        where T: Word
+    {
+        unsafe { ops::set_field(self.ptr, first_bit, bit_count, value) }
+    }
+
+    /// Like [`set_field`](#method.set_field), but returns an error instead of panicking.
+    #[inline(always)]
+    pub fn try_set_field(self, first_bit: u8, bit_count: u8, value: T) -> Result<(), Error>
+        where T: Word
+    {
+        unsafe { ops::try_set_field(self.ptr, first_bit, bit_count, value) }
+    }
+
+    /// Sets a sub-field, given as const generic parameters, of the pointed-to value.
+    /// See [`VolatileCell::set_field_const`].
+    ///
+    /// [`VolatileCell::set_field_const`]: struct.VolatileCell.html#method.set_field_const
+    #[inline(always)]
+    pub fn set_field_const<const FIRST_BIT: u8, const BIT_COUNT: u8>(self, value: T)
+        where T: Word
+    {
+        unsafe { ops::set_field_const::<T, FIRST_BIT, BIT_COUNT>(self.ptr, value) }
+    }
+
+    /// Sets a collection of bits of the pointed-to value.
+    /// See [`VolatileCell::set_bits`].
+    ///
+    /// [`VolatileCell::set_bits`]: struct.VolatileCell.html#method.set_bits
+    #[inline(always)]
+    pub fn set_bits(self, bits_to_set: T)
+        where T: Word
+    {
+        unsafe { ops::set_bits(self.ptr, bits_to_set) }
+    }
+
+    /// Clears a collection of bits of the pointed-to value.
+    /// See [`VolatileCell::clear_bits`].
+    ///
+    /// [`VolatileCell::clear_bits`]: struct.VolatileCell.html#method.clear_bits
+    #[inline(always)]
+    pub fn clear_bits(self, bits_to_clear: T)
+        where T: Word
+    {
+        unsafe { ops::clear_bits(self.ptr, bits_to_clear) }
+    }
+
+    /// Inverts a collection of bits of the pointed-to value.
+    /// See [`VolatileCell::invert_bits`].
+    ///
+    /// [`VolatileCell::invert_bits`]: struct.VolatileCell.html#method.invert_bits
+    #[inline(always)]
+    pub fn invert_bits(self, bits_to_invert: T)
+        where T: Word
+    {
+        unsafe { ops::invert_bits(self.ptr, bits_to_invert) }
+    }
+
+    /// Reads a single bit of the pointed-to value and clears it with the
+    /// bit-manipulation-engine. See [`VolatileCell::load_clear_bit`].
+    ///
+    /// [`VolatileCell::load_clear_bit`]: struct.VolatileCell.html#method.load_clear_bit
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn load_clear_bit(self, bit: u8) -> T
+        where T: Copy
+    {
+        unsafe { ptr::read_volatile(BmeOperation::LoadClearBit{bit}.wrap_pointer(self.ptr)) }
+    }
+
+    /// Like [`load_clear_bit`](#method.load_clear_bit), but returns an error instead of
+    /// panicking.
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn try_load_clear_bit(self, bit: u8) -> Result<T, Error>
+        where T: Copy
+    {
+        let lac_ptr = BmeOperation::LoadClearBit{bit}.try_wrap_pointer(self.ptr)?;
+        Ok(unsafe { ptr::read_volatile(lac_ptr) })
+    }
+
+    /// Reads a single bit of the pointed-to value and sets it with the bit-manipulation-engine.
+    /// See [`VolatileCell::load_set_bit`].
+    ///
+    /// [`VolatileCell::load_set_bit`]: struct.VolatileCell.html#method.load_set_bit
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn load_set_bit(self, bit: u8) -> T
+        where T: Copy
+    {
+        unsafe { ptr::read_volatile(BmeOperation::LoadSetBit{bit}.wrap_pointer(self.ptr)) }
+    }
+
+    /// Like [`load_set_bit`](#method.load_set_bit), but returns an error instead of panicking.
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn try_load_set_bit(self, bit: u8) -> Result<T, Error>
+        where T: Copy
+    {
+        let las_ptr = BmeOperation::LoadSetBit{bit}.try_wrap_pointer(self.ptr)?;
+        Ok(unsafe { ptr::read_volatile(las_ptr) })
+    }
+
+    /// Reads a sub-field of the pointed-to value with the bit-manipulation-engine.
+    /// See [`VolatileCell::get_field`].
+    ///
+    /// [`VolatileCell::get_field`]: struct.VolatileCell.html#method.get_field
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn get_field(self, first_bit: u8, bit_count: u8) -> T
+        where T: Copy
+    {
+        let op = BmeOperation::GetField{first_bit, bit_count};
+        unsafe { ptr::read_volatile(op.wrap_pointer(self.ptr)) }
+    }
+
+    /// Like [`get_field`](#method.get_field), but returns an error instead of panicking.
+    #[inline(always)]
+    #[cfg(feature = "bit-manipulation")]
+    pub fn try_get_field(self, first_bit: u8, bit_count: u8) -> Result<T, Error>
+        where T: Copy
+    {
+        let op = BmeOperation::GetField{first_bit, bit_count};
+        let ubfx_ptr = op.try_wrap_pointer(self.ptr)?;
+        Ok(unsafe { ptr::read_volatile(ubfx_ptr) })
+    }
+
+    /// Sets a single bit of the pointed-to value to `value`.
+    /// See [`VolatileCell::set_bit`].
+    ///
+    /// [`VolatileCell::set_bit`]: struct.VolatileCell.html#method.set_bit
+    #[inline(always)]
+    pub fn set_bit(self, bit_to_set: u8, value: bool)
+        where T: Word
+    {
+        unsafe { ops::set_bit(self.ptr, bit_to_set, value) }
+    }
+
+    /// Like [`set_bit`](#method.set_bit), but returns an error instead of panicking
+    /// if the bit does not exist in `T`.
+    #[inline(always)]
+    pub fn try_set_bit(self, bit_to_set: u8, value: bool) -> Result<(), Error>
+        where T: Word
+    {
+        unsafe { ops::try_set_bit(self.ptr, bit_to_set, value) }
+    }
+
+    /// Clears a single bit of the pointed-to value.
+    /// See [`VolatileCell::clear_bit`].
+    ///
+    /// [`VolatileCell::clear_bit`]: struct.VolatileCell.html#method.clear_bit
+    #[inline(always)]
+    pub fn clear_bit(self, bit_to_clear: u8)
+        where T: Word
+    {
+        unsafe { ops::set_bit(self.ptr, bit_to_clear, false) }
+    }
+
+    /// Like [`clear_bit`](#method.clear_bit), but returns an error instead of panicking
+    /// if the bit does not exist in `T`.
+    #[inline(always)]
+    pub fn try_clear_bit(self, bit_to_clear: u8) -> Result<(), Error>
+        where T: Word
+    {
+        unsafe { ops::try_set_bit(self.ptr, bit_to_clear, false) }
+    }
+
+    /// Inverts a single bit of the pointed-to value.
+    /// See [`VolatileCell::toggle_bit`].
+    ///
+    /// [`VolatileCell::toggle_bit`]: struct.VolatileCell.html#method.toggle_bit
+    #[inline(always)]
+    pub fn toggle_bit(self, bit_to_toggle: u8)
+        where T: Word
+    {
+        unsafe { ops::toggle_bit(self.ptr, bit_to_toggle) }
+    }
+
+    /// Like [`toggle_bit`](#method.toggle_bit), but returns an error instead of panicking
+    /// if the bit does not exist in `T`.
+    #[inline(always)]
+    pub fn try_toggle_bit(self, bit_to_toggle: u8) -> Result<(), Error>
+        where T: Word
+    {
+        unsafe { ops::try_toggle_bit(self.ptr, bit_to_toggle) }
+    }
+
+    /// Returns a single bit of the pointed-to value.
+    /// See [`VolatileCell::get_bit`].
+    ///
+    /// [`VolatileCell::get_bit`]: struct.VolatileCell.html#method.get_bit
+    #[inline(always)]
+    pub fn get_bit(self, bit_to_get: u8) -> bool
+        where T: Word
+    {
+        unsafe { ops::get_bit(self.ptr, bit_to_get) }
+    }
+
+    /// Like [`get_bit`](#method.get_bit), but returns an error instead of panicking
+    /// if the bit does not exist in `T`.
+    #[inline(always)]
+    pub fn try_get_bit(self, bit_to_get: u8) -> Result<bool, Error>
+        where T: Word
+    {
+        unsafe { ops::try_get_bit(self.ptr, bit_to_get) }
+    }
+}
+
+impl<T> Clone for VolPtr<T> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for VolPtr<T> {}
+
+impl<T> fmt::Debug for VolPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "VolPtr({:p})", self.ptr)
+    }
+}
+
+#[cfg(test)]
+mod test_volptr {
+    use super::*;
+
+    #[test]
+    fn test_get_set() {
+        let mut raw = 0x55u8;
+        let p = unsafe { VolPtr::new(&mut raw as *mut u8) };
+        assert_eq!(p.get(), 0x55);
+        let q = p;
+        q.set(0xaa);
+        assert_eq!(p.get(), 0xaa);
+    }
+}
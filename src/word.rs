@@ -0,0 +1,80 @@
+//! Integer types that registers can hold
+
+use core::ops::{BitAnd, BitOr, BitXor, Not};
+
+/// An unsigned integer that can be manipulated bit by bit
+///
+/// The bit manipulation operations of [`VolatileCell`] use this to fall back to a plain
+/// read-modify-write when no hardware engine can perform them.
+///
+/// [`VolatileCell`]: struct.VolatileCell.html
+pub trait Word:
+    Copy + Eq +
+    BitAnd<Output = Self> + BitOr<Output = Self> + BitXor<Output = Self> + Not<Output = Self>
+{
+    /// The size of the type in bits
+    const BITS: u32;
+
+    /// The value with no bits set
+    const ZERO: Self;
+
+    /// Returns a value with only bit `bit` set
+    ///
+    /// `bit` must be less than `BITS`.
+    fn bit(bit: u8) -> Self;
+
+    /// Returns a value with the `bit_count` bits starting at `first_bit` set
+    ///
+    /// `bit_count` must be at least 1, and the field must fit in `BITS`.
+    fn field_mask(first_bit: u8, bit_count: u8) -> Self;
+
+    /// Returns the value shifted left by `bits`
+    ///
+    /// `bits` must be less than `BITS`.
+    fn shift_left(self, bits: u32) -> Self;
+}
+
+macro_rules! word {
+    ($($t:ty),*) => {
+        $(
+            impl Word for $t {
+                const BITS: u32 = <$t>::BITS;
+                const ZERO: Self = 0;
+
+                #[inline(always)]
+                fn bit(bit: u8) -> Self {
+                    1 << bit
+                }
+
+                #[inline(always)]
+                fn field_mask(first_bit: u8, bit_count: u8) -> Self {
+                    (!0 >> (Self::BITS - u32::from(bit_count))) << first_bit
+                }
+
+                #[inline(always)]
+                fn shift_left(self, bits: u32) -> Self {
+                    self << bits
+                }
+            }
+        )*
+    }
+}
+
+word!(u8, u16, u32, u64, usize);
+
+#[cfg(test)]
+mod test_word {
+    use super::*;
+
+    #[test]
+    fn test_field_mask() {
+        assert_eq!(u8::field_mask(0, 8), 0xff);
+        assert_eq!(u8::field_mask(6, 2), 0xc0);
+        assert_eq!(u16::field_mask(4, 4), 0x00f0);
+        assert_eq!(u32::field_mask(31, 1), 0x8000_0000);
+        assert_eq!(u64::field_mask(0, 64), !0);
+        assert_eq!(u32::bit(5), 0x20);
+        assert_eq!(u16::ZERO, 0);
+        assert_eq!(0x00a5u16.shift_left(8), 0xa500);
+    }
+}